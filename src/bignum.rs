@@ -0,0 +1,173 @@
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+use rand::Rng;
+
+const SMALL_PRIMES: [u32; 25] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+];
+
+pub fn random_biguint_bits<R: Rng + ?Sized>(bits: u64, rng: &mut R) -> BigUint {
+    // uniformly random integer in [0, 2^bits)
+    let mut buf = vec![0u8; bits.div_ceil(8) as usize];
+    rng.fill_bytes(&mut buf);
+    let excess = buf.len() as u64 * 8 - bits;
+    if let Some(first) = buf.first_mut() {
+        *first &= 0xff >> excess;
+    }
+    BigUint::from_bytes_be(&buf)
+}
+
+pub fn random_biguint_below<R: Rng + ?Sized>(bound: &BigUint, rng: &mut R) -> BigUint {
+    // rejection sampling keeps the result uniform in [0, bound)
+    assert!(!bound.is_zero(), "Bound must be non-zero.");
+    let bits = bound.bits();
+    loop {
+        let candidate = random_biguint_bits(bits, rng);
+        if &candidate < bound {
+            return candidate;
+        }
+    }
+}
+
+pub fn random_biguint_range<R: Rng + ?Sized>(
+    low: &BigUint,
+    high: &BigUint,
+    rng: &mut R,
+) -> BigUint {
+    // uniformly random integer in [low, high)
+    assert!(low < high, "Empty range.");
+    low + random_biguint_below(&(high - low), rng)
+}
+
+pub fn is_probable_prime<R: Rng + ?Sized>(n: &BigUint, rounds: u32, rng: &mut R) -> bool {
+    let two = BigUint::from(2u32);
+    if n < &two {
+        return false;
+    }
+    for p in SMALL_PRIMES.iter().map(|&p| BigUint::from(p)) {
+        if n == &p {
+            return true;
+        }
+        if (n % &p).is_zero() {
+            return false;
+        }
+    }
+    // n - 1 = 2^s * d with d odd
+    let n_minus_one = n - 1u32;
+    let s = n_minus_one.trailing_zeros().unwrap();
+    let d = &n_minus_one >> s;
+    'witness: for _ in 0..rounds {
+        let a = random_biguint_range(&two, &n_minus_one, rng);
+        let mut x = a.modpow(&d, n);
+        if x.is_one() || x == n_minus_one {
+            continue;
+        }
+        for _ in 1..s {
+            x = x.modpow(&two, n);
+            if x == n_minus_one {
+                continue 'witness;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+pub fn generate_prime<R: Rng + ?Sized>(bits: u64, rng: &mut R) -> BigUint {
+    // the top two bits are set so that the product of two such primes has exactly 2 * bits bits
+    assert!(bits >= 8, "Primes must be at least 8 bits.");
+    loop {
+        let mut candidate = random_biguint_bits(bits, rng);
+        candidate.set_bit(bits - 1, true);
+        candidate.set_bit(bits - 2, true);
+        candidate.set_bit(0, true);
+        if is_probable_prime(&candidate, 40, rng) {
+            return candidate;
+        }
+    }
+}
+
+pub fn crt(residues: &[BigUint], moduli: &[BigUint]) -> (BigUint, BigUint) {
+    // returns (x, N) where x = residues[i] mod moduli[i] for every i and N is the product of the
+    // moduli, which must be pairwise coprime
+    assert_eq!(residues.len(), moduli.len());
+    let product: BigUint = moduli.iter().product();
+    let mut x = BigUint::zero();
+    for (r, m) in residues.iter().zip(moduli) {
+        let ms = &product / m;
+        let inv = (&ms % m)
+            .modinv(m)
+            .expect("Moduli must be pairwise coprime.");
+        x += r * &ms * inv;
+    }
+    (x % &product, product)
+}
+
+pub fn biguint_to_bytes_padded(n: &BigUint, len: usize) -> Vec<u8> {
+    // big-endian, left-padded with zeros to len bytes
+    let bytes = n.to_bytes_be();
+    assert!(bytes.len() <= len, "Integer does not fit in {len} bytes.");
+    [vec![0u8; len - bytes.len()], bytes].concat()
+}
+
+pub fn mod_sub(a: &BigUint, b: &BigUint, m: &BigUint) -> BigUint {
+    // (a - b) mod m without going through signed integers
+    ((a % m) + m - (b % m)) % m
+}
+
+pub fn mod_sqrt(a: &BigUint, p: &BigUint) -> Option<BigUint> {
+    // Tonelli-Shanks for an odd prime p. returns the smaller of the two roots, or None if a is
+    // not a square mod p
+    let a = a % p;
+    if a.is_zero() {
+        return Some(a);
+    }
+    let one = BigUint::one();
+    let p_minus_one = p - 1u32;
+    let half = &p_minus_one >> 1;
+    if a.modpow(&half, p) != one {
+        return None;
+    }
+    // p - 1 = 2^s * q with q odd, and z any non-square
+    let s = p_minus_one.trailing_zeros().unwrap();
+    let q = &p_minus_one >> s;
+    let mut z = BigUint::from(2u32);
+    while z.modpow(&half, p) == one {
+        z += 1u32;
+    }
+    let mut m = s;
+    let mut c = z.modpow(&q, p);
+    let mut t = a.modpow(&q, p);
+    let mut r = a.modpow(&((&q + 1u32) >> 1), p);
+    while !t.is_one() {
+        // the least i with t^(2^i) = 1
+        let mut i = 0;
+        let mut t2 = t.clone();
+        while !t2.is_one() {
+            t2 = &t2 * &t2 % p;
+            i += 1;
+        }
+        let b = c.modpow(&(BigUint::one() << (m - i - 1)), p);
+        m = i;
+        c = &b * &b % p;
+        t = t * &c % p;
+        r = r * b % p;
+    }
+    let other = p - &r;
+    Some(r.min(other))
+}
+
+pub fn small_prime_factors(n: &BigUint, bound: u64) -> Vec<u64> {
+    // the distinct primes below bound that divide n, by trial division
+    let mut n = n.clone();
+    let mut factors = vec![];
+    for d in 2..bound {
+        if (&n % d).is_zero() {
+            factors.push(d);
+            while (&n % d).is_zero() {
+                n /= d;
+            }
+        }
+    }
+    factors
+}
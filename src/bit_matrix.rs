@@ -0,0 +1,219 @@
+// dense matrices over GF(2), each row packed into u64 words. vectors are plain Vec<bool>.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BitMatrix {
+    pub rows: usize,
+    pub cols: usize,
+    words: Vec<u64>,
+}
+
+impl BitMatrix {
+    pub fn zero(rows: usize, cols: usize) -> Self {
+        BitMatrix {
+            rows,
+            cols,
+            words: vec![0; rows * cols.div_ceil(64)],
+        }
+    }
+
+    pub fn identity(n: usize) -> Self {
+        let mut m = BitMatrix::zero(n, n);
+        for i in 0..n {
+            m.set(i, i, true);
+        }
+        m
+    }
+
+    pub fn from_rows(rows: &[Vec<bool>]) -> Self {
+        let cols = rows.first().map_or(0, |r| r.len());
+        let mut m = BitMatrix::zero(rows.len(), cols);
+        for (i, row) in rows.iter().enumerate() {
+            assert_eq!(row.len(), cols, "Rows must all be the same length.");
+            for (j, &bit) in row.iter().enumerate() {
+                m.set(i, j, bit);
+            }
+        }
+        m
+    }
+
+    pub fn from_columns(columns: &[Vec<bool>]) -> Self {
+        BitMatrix::from_rows(columns).transpose()
+    }
+
+    fn stride(&self) -> usize {
+        self.cols.div_ceil(64)
+    }
+
+    fn row_words(&self, r: usize) -> &[u64] {
+        let stride = self.stride();
+        &self.words[r * stride..(r + 1) * stride]
+    }
+
+    pub fn get(&self, r: usize, c: usize) -> bool {
+        assert!(r < self.rows && c < self.cols, "Index out of range.");
+        (self.words[r * self.stride() + c / 64] >> (c % 64)) & 1 == 1
+    }
+
+    pub fn set(&mut self, r: usize, c: usize, bit: bool) {
+        assert!(r < self.rows && c < self.cols, "Index out of range.");
+        let i = r * self.stride() + c / 64;
+        if bit {
+            self.words[i] |= 1 << (c % 64);
+        } else {
+            self.words[i] &= !(1 << (c % 64));
+        }
+    }
+
+    pub fn row(&self, r: usize) -> Vec<bool> {
+        (0..self.cols).map(|c| self.get(r, c)).collect()
+    }
+
+    pub fn column(&self, c: usize) -> Vec<bool> {
+        (0..self.rows).map(|r| self.get(r, c)).collect()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.words.iter().all(|&w| w == 0)
+    }
+
+    pub fn transpose(&self) -> BitMatrix {
+        let mut t = BitMatrix::zero(self.cols, self.rows);
+        for r in 0..self.rows {
+            for c in 0..self.cols {
+                if self.get(r, c) {
+                    t.set(c, r, true);
+                }
+            }
+        }
+        t
+    }
+
+    pub fn add(&self, other: &BitMatrix) -> BitMatrix {
+        assert_eq!((self.rows, self.cols), (other.rows, other.cols));
+        BitMatrix {
+            words: self
+                .words
+                .iter()
+                .zip(&other.words)
+                .map(|(a, b)| a ^ b)
+                .collect(),
+            ..self.clone()
+        }
+    }
+
+    pub fn mul(&self, other: &BitMatrix) -> BitMatrix {
+        // row r of the product is the xor of the rows of other picked out by row r of self
+        assert_eq!(self.cols, other.rows, "Dimension mismatch.");
+        let mut product = BitMatrix::zero(self.rows, other.cols);
+        let stride = product.stride();
+        for r in 0..self.rows {
+            for k in (0..self.cols).filter(|&k| self.get(r, k)) {
+                let (dst, src) = (r * stride, other.row_words(k));
+                for (w, s) in product.words[dst..dst + stride].iter_mut().zip(src) {
+                    *w ^= s;
+                }
+            }
+        }
+        product
+    }
+
+    pub fn mul_vector(&self, v: &[bool]) -> Vec<bool> {
+        assert_eq!(self.cols, v.len(), "Dimension mismatch.");
+        (0..self.rows)
+            .map(|r| (0..self.cols).filter(|&c| v[c] && self.get(r, c)).count() % 2 == 1)
+            .collect()
+    }
+
+    pub fn stack(&self, other: &BitMatrix) -> BitMatrix {
+        // self on top of other
+        assert_eq!(self.cols, other.cols, "Dimension mismatch.");
+        BitMatrix {
+            rows: self.rows + other.rows,
+            cols: self.cols,
+            words: [self.words.as_slice(), &other.words].concat(),
+        }
+    }
+
+    pub fn select_rows(&self, rows: std::ops::Range<usize>) -> BitMatrix {
+        let stride = self.stride();
+        BitMatrix {
+            rows: rows.len(),
+            cols: self.cols,
+            words: self.words[rows.start * stride..rows.end * stride].to_vec(),
+        }
+    }
+
+    pub fn reduce(&mut self) -> Vec<usize> {
+        // Gauss-Jordan elimination in place to reduced row echelon form. returns the pivot
+        // column of each of the first rank rows
+        let stride = self.stride();
+        let mut pivots = vec![];
+        for c in 0..self.cols {
+            let r = pivots.len();
+            if r == self.rows {
+                break;
+            }
+            let Some(p) = (r..self.rows).find(|&p| self.get(p, c)) else {
+                continue;
+            };
+            for w in 0..stride {
+                self.words.swap(r * stride + w, p * stride + w);
+            }
+            let (word, bit) = (c / 64, 1 << (c % 64));
+            let pivot = self.words[r * stride + word..(r + 1) * stride].to_vec();
+            for (other, row) in self.words.chunks_exact_mut(stride).enumerate() {
+                if other == r || row[word] & bit == 0 {
+                    continue;
+                }
+                for (w, p) in row[word..].iter_mut().zip(&pivot) {
+                    *w ^= p;
+                }
+            }
+            pivots.push(c);
+        }
+        pivots
+    }
+
+    pub fn rank(&self) -> usize {
+        self.clone().reduce().len()
+    }
+
+    pub fn kernel(&self) -> Vec<Vec<bool>> {
+        // a basis of { v : self * v = 0 }, one vector per free column
+        let mut m = self.clone();
+        let pivots = m.reduce();
+        let mut is_pivot = vec![false; self.cols];
+        for &p in &pivots {
+            is_pivot[p] = true;
+        }
+        (0..self.cols)
+            .filter(|&f| !is_pivot[f])
+            .map(|f| {
+                let mut v = vec![false; self.cols];
+                v[f] = true;
+                for (r, &p) in pivots.iter().enumerate() {
+                    v[p] = m.get(r, f);
+                }
+                v
+            })
+            .collect()
+    }
+
+    pub fn solve(&self, b: &[bool]) -> Option<Vec<bool>> {
+        // some v with self * v = b, or None if there is none
+        assert_eq!(self.rows, b.len(), "Dimension mismatch.");
+        let columns: Vec<Vec<bool>> = (0..self.cols)
+            .map(|c| self.column(c))
+            .chain([b.to_vec()])
+            .collect();
+        let mut augmented = BitMatrix::from_columns(&columns);
+        let pivots = augmented.reduce();
+        if pivots.last() == Some(&self.cols) {
+            return None;
+        }
+        let mut v = vec![false; self.cols];
+        for (r, &p) in pivots.iter().enumerate() {
+            v[p] = augmented.get(r, self.cols);
+        }
+        Some(v)
+    }
+}
@@ -0,0 +1,116 @@
+use crate::{encrypt_aes_128_cbc, pkcs7_pad, xor_bytes};
+
+// key and IV for the CBC-MAC based "hash" from challenge 50
+pub const CBC_MAC_HASH_KEY: &[u8; 16] = b"YELLOW SUBMARINE";
+pub const CBC_MAC_HASH_IV: &[u8; 16] = &[0; 16];
+
+pub fn cbc_mac(message: &[u8], key: &[u8], iv: &[u8]) -> Vec<u8> {
+    // CBC encrypt the padded message and keep only the last block
+    let padded = pkcs7_pad(message.to_vec(), 16);
+    let encrypted = encrypt_aes_128_cbc(&padded, key, iv);
+    encrypted[encrypted.len() - 16..].to_vec()
+}
+
+pub fn verify_cbc_mac(message: &[u8], key: &[u8], iv: &[u8], mac: &[u8]) -> bool {
+    cbc_mac(message, key, iv) == mac
+}
+
+fn cbc_state(block_aligned: &[u8], key: &[u8], iv: &[u8]) -> Vec<u8> {
+    // chaining value after CBC encrypting whole blocks without padding
+    assert_eq!(block_aligned.len() % 16, 0, "Input must be block aligned.");
+    let encrypted = encrypt_aes_128_cbc(block_aligned, key, iv);
+    if encrypted.is_empty() {
+        iv.to_vec()
+    } else {
+        encrypted[encrypted.len() - 16..].to_vec()
+    }
+}
+
+pub fn format_transfer_request(from: u32, to: u32, amount: u64) -> Vec<u8> {
+    format!("from={from}&to={to}&amount={amount}").into_bytes()
+}
+
+pub fn format_multi_transfer_request(from: u32, transactions: &[(u32, u64)]) -> Vec<u8> {
+    let tx_list: Vec<String> = transactions
+        .iter()
+        .map(|(to, amount)| format!("{to}:{amount}"))
+        .collect();
+    format!("from={from}&tx_list={}", tx_list.join(";")).into_bytes()
+}
+
+pub fn forge_cbc_mac_iv(message: &[u8], iv: &[u8], forged_message: &[u8]) -> Vec<u8> {
+    // when the IV travels with the message, any change to the first block can be cancelled
+    // out in the IV: returns the IV that gives forged_message the MAC of message
+    assert_eq!(
+        message.len(),
+        forged_message.len(),
+        "Messages must be the same length."
+    );
+    let first_len = 16.min(message.len());
+    assert!(
+        message[first_len..] == forged_message[first_len..],
+        "Only the first block may change."
+    );
+    let delta = xor_bytes(&message[..first_len], &forged_message[..first_len]);
+    let mut forged_iv = iv.to_vec();
+    for (b, d) in forged_iv.iter_mut().zip(delta) {
+        *b ^= d;
+    }
+    forged_iv
+}
+
+pub fn cbc_mac_length_extension(
+    message: &[u8],
+    mac: &[u8],
+    extension: &[u8],
+    iv: &[u8],
+) -> Vec<u8> {
+    // with a fixed IV, message || padding || (first block of extension ^ mac ^ iv) || rest of
+    // extension has the same MAC as extension on its own
+    assert!(
+        extension.len() >= 16,
+        "Extension must be at least one block."
+    );
+    let glue: Vec<u8> = xor_bytes(&xor_bytes(&extension[..16], mac), iv);
+    [
+        pkcs7_pad(message.to_vec(), 16),
+        glue,
+        extension[16..].to_vec(),
+    ]
+    .concat()
+}
+
+pub fn cbc_mac_hash(message: &[u8]) -> Vec<u8> {
+    cbc_mac(message, CBC_MAC_HASH_KEY, CBC_MAC_HASH_IV)
+}
+
+pub fn forge_cbc_mac_hash_collision<F>(target: &[u8], prefix: &[u8], is_acceptable: F) -> Vec<u8>
+where
+    F: Fn(&[u8]) -> bool,
+{
+    // the key is public, so we can compute the chaining value after any block-aligned prefix and
+    // glue the rest of target on. prefix is filled out to a block boundary plus one block with a
+    // counter, which is bumped until is_acceptable likes the glue block.
+    assert!(target.len() >= 16, "Target must be at least one block.");
+    for counter in 0u64.. {
+        let mut aligned = prefix.to_vec();
+        let fill_len = (16 - aligned.len() % 16) % 16 + 16;
+        let mut filler = format!("{counter:0>width$}", width = fill_len).into_bytes();
+        filler.truncate(fill_len);
+        aligned.append(&mut filler);
+        let state = cbc_state(&aligned, CBC_MAC_HASH_KEY, CBC_MAC_HASH_IV);
+        let glue = xor_bytes(&xor_bytes(&target[..16], &state), CBC_MAC_HASH_IV);
+        if is_acceptable(&glue) {
+            return [aligned, glue, target[16..].to_vec()].concat();
+        }
+    }
+    unreachable!()
+}
+
+pub fn forge_javascript_collision(target: &[u8], payload: &[u8]) -> Vec<u8> {
+    // comments out everything after payload, so the glue block must not end the line
+    let prefix = [payload, b"//"].concat();
+    forge_cbc_mac_hash_collision(target, &prefix, |glue| {
+        !glue.iter().any(|b| [b'\n', b'\r'].contains(b))
+    })
+}
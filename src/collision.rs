@@ -0,0 +1,267 @@
+use crate::encrypt_aes_128_block;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+// the collision finders work on functions from n-bit values to n-bit values. Any hash can be
+// turned into one with iterated_function, and a collision found there is a collision of the
+// hash on the two encoded inputs.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Collision {
+    pub a: u64,
+    pub b: u64,
+    pub value: u64,
+    // how much work and memory the search took, for comparing the methods
+    pub evaluations: u64,
+    pub stored: usize,
+}
+
+pub fn mask(bits: u32) -> u64 {
+    if bits >= 64 {
+        u64::MAX
+    } else {
+        (1 << bits) - 1
+    }
+}
+
+pub fn iterated_function<H>(hash: H, bits: u32) -> impl Fn(u64) -> u64
+where
+    H: Fn(&[u8]) -> Vec<u8>,
+{
+    // x is hashed as 8 big endian bytes and the first bits of the digest are the result
+    assert!((1..=64).contains(&bits), "Output must be 1 to 64 bits.");
+    move |x| {
+        let digest = hash(&x.to_be_bytes());
+        let mut buf = [0u8; 8];
+        let len = digest.len().min(8);
+        buf[..len].copy_from_slice(&digest[..len]);
+        (u64::from_be_bytes(buf) >> (64 - bits)) & mask(bits)
+    }
+}
+
+pub fn truncated_aes_hash(message: &[u8]) -> Vec<u8> {
+    // a toy hash for the collision finders: AES under a fixed key of the message as one block
+    // (zero padded, at most 16 bytes)
+    assert!(message.len() <= 16, "Message must fit in one block.");
+    let block = [message, &vec![0u8; 16 - message.len()]].concat();
+    encrypt_aes_128_block(&block, b"YELLOW SUBMARINE")
+}
+
+pub fn birthday_collision<F>(f: F, start: u64) -> Collision
+where
+    F: Fn(u64) -> u64,
+{
+    // evaluate f on start, start + 1, ... and remember every output until one repeats.
+    // about 2^(n/2) evaluations and as many table entries.
+    let mut seen: HashMap<u64, u64> = HashMap::new();
+    for x in start.. {
+        let value = f(x);
+        if let Some(&a) = seen.get(&value) {
+            return Collision {
+                a,
+                b: x,
+                value,
+                evaluations: x - start + 1,
+                stored: seen.len(),
+            };
+        }
+        seen.insert(value, x);
+    }
+    unreachable!()
+}
+
+fn collision_from_tail<F>(
+    f: &F,
+    mut tortoise: u64,
+    mut hare: u64,
+    evaluations: u64,
+) -> Option<Collision>
+where
+    F: Fn(u64) -> u64,
+{
+    // tortoise starts on the tail and hare the same distance before the meeting point on the
+    // cycle; stepping both together, the values just before they first agree collide
+    let mut evaluations = evaluations;
+    if tortoise == hare {
+        // the start was already on the cycle, so there is no tail to collide on
+        return None;
+    }
+    loop {
+        let next_tortoise = f(tortoise);
+        let next_hare = f(hare);
+        evaluations += 2;
+        if next_tortoise == next_hare {
+            return Some(Collision {
+                a: tortoise,
+                b: hare,
+                value: next_tortoise,
+                evaluations,
+                stored: 0,
+            });
+        }
+        tortoise = next_tortoise;
+        hare = next_hare;
+    }
+}
+
+pub fn floyd_collision<F>(f: F, x0: u64) -> Option<Collision>
+where
+    F: Fn(u64) -> u64,
+{
+    // the rho shaped walk x0, f(x0), ... enters a cycle; where the tail joins the cycle two
+    // different values map to the same one. constant memory, about 3 * 2^(n/2) evaluations.
+    let mut tortoise = f(x0);
+    let mut hare = f(f(x0));
+    let mut evaluations = 3;
+    while tortoise != hare {
+        tortoise = f(tortoise);
+        hare = f(f(hare));
+        evaluations += 3;
+    }
+    collision_from_tail(&f, x0, hare, evaluations)
+}
+
+pub fn brent_collision<F>(f: F, x0: u64) -> Option<Collision>
+where
+    F: Fn(u64) -> u64,
+{
+    // like floyd_collision, but finds the cycle length first by teleporting the tortoise at
+    // powers of two, which needs fewer evaluations
+    let mut power = 1u64;
+    let mut lambda = 1u64;
+    let mut tortoise = x0;
+    let mut hare = f(x0);
+    let mut evaluations = 1;
+    while tortoise != hare {
+        if power == lambda {
+            tortoise = hare;
+            power *= 2;
+            lambda = 0;
+        }
+        hare = f(hare);
+        evaluations += 1;
+        lambda += 1;
+    }
+    // put the hare lambda steps ahead of x0, then walk both to where the tail meets the cycle
+    let mut hare = x0;
+    for _ in 0..lambda {
+        hare = f(hare);
+    }
+    evaluations += lambda;
+    collision_from_tail(&f, x0, hare, evaluations)
+}
+
+fn walk_to_distinguished_point<F>(
+    f: &F,
+    start: u64,
+    dp_mask: u64,
+    max_len: u64,
+) -> Option<(u64, u64)>
+where
+    F: Fn(u64) -> u64,
+{
+    // returns (distinguished point, steps taken), or None if the walk got too long, which
+    // usually means it is stuck in a cycle without a distinguished point
+    let mut x = start;
+    for steps in 1..=max_len {
+        x = f(x);
+        if x & dp_mask == 0 {
+            return Some((x, steps));
+        }
+    }
+    None
+}
+
+fn collision_from_walks<F>(f: &F, walk_a: (u64, u64), walk_b: (u64, u64)) -> Option<(u64, u64, u64)>
+where
+    F: Fn(u64) -> u64,
+{
+    // two walks (start, length) that end in the same distinguished point merged somewhere:
+    // line them up at the same distance from the end and step together until they agree
+    let ((mut a, len_a), (mut b, len_b)) = if walk_a.1 >= walk_b.1 {
+        (walk_a, walk_b)
+    } else {
+        (walk_b, walk_a)
+    };
+    for _ in 0..len_a - len_b {
+        a = f(a);
+    }
+    if a == b {
+        // one walk started on the other, so they never merged
+        return None;
+    }
+    loop {
+        let next_a = f(a);
+        let next_b = f(b);
+        if next_a == next_b {
+            return Some((a, b, next_a));
+        }
+        a = next_a;
+        b = next_b;
+    }
+}
+
+pub fn distinguished_point_collision<F>(
+    f: F,
+    bits: u32,
+    dp_bits: u32,
+    threads: usize,
+    seed: u64,
+) -> Collision
+where
+    F: Fn(u64) -> u64 + Sync,
+{
+    // van Oorschot-Wiener parallel collision search: every thread walks from random starts until
+    // it hits a point whose low dp_bits are zero and files it in a shared table. Two walks ending
+    // in the same point have merged, and retracing them gives the collision. Memory is about
+    // 2^(n/2 - dp_bits) table entries, and the walks parallelise perfectly.
+    assert!(threads > 0, "Need at least one thread.");
+    let dp_mask = mask(dp_bits);
+    let max_len = 20 << dp_bits;
+    let table: Mutex<HashMap<u64, (u64, u64)>> = Mutex::new(HashMap::new());
+    let found: Mutex<Option<Collision>> = Mutex::new(None);
+    let done = AtomicBool::new(false);
+    let evaluations = AtomicU64::new(0);
+    std::thread::scope(|scope| {
+        for thread in 0..threads {
+            let (f, table, found, done, evaluations) = (&f, &table, &found, &done, &evaluations);
+            scope.spawn(move || {
+                let mut rng = StdRng::seed_from_u64(seed ^ (thread as u64) << 48);
+                while !done.load(Ordering::Relaxed) {
+                    let start = rng.random::<u64>() & mask(bits);
+                    let Some((point, len)) =
+                        walk_to_distinguished_point(f, start, dp_mask, max_len)
+                    else {
+                        evaluations.fetch_add(max_len, Ordering::Relaxed);
+                        continue;
+                    };
+                    evaluations.fetch_add(len, Ordering::Relaxed);
+                    let previous = table.lock().unwrap().insert(point, (start, len));
+                    let Some(previous) = previous else {
+                        continue;
+                    };
+                    if let Some((a, b, value)) = collision_from_walks(f, previous, (start, len)) {
+                        let mut found = found.lock().unwrap();
+                        if found.is_none() {
+                            *found = Some(Collision {
+                                a,
+                                b,
+                                value,
+                                evaluations: 0,
+                                stored: 0,
+                            });
+                        }
+                        done.store(true, Ordering::Relaxed);
+                    }
+                }
+            });
+        }
+    });
+    let mut collision = found.into_inner().unwrap().unwrap();
+    collision.evaluations = evaluations.into_inner();
+    collision.stored = table.into_inner().unwrap().len();
+    collision
+}
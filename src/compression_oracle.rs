@@ -0,0 +1,136 @@
+use crate::{aes_128_ctr, encrypt_aes_128_cbc, pkcs7_pad, rand_bytes};
+use flate2::Compression;
+use flate2::write::DeflateEncoder;
+use std::io::Write;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompressionOracleCipher {
+    Ctr,
+    Cbc,
+}
+
+pub struct CompressionOracle {
+    session_id: Vec<u8>,
+    cipher: CompressionOracleCipher,
+}
+
+pub fn deflate(input_bytes: &[u8]) -> Vec<u8> {
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(input_bytes)
+        .expect("Unable to compress bytes.");
+    encoder.finish().expect("Unable to compress bytes.")
+}
+
+impl CompressionOracle {
+    pub fn new(session_id: &[u8], cipher: CompressionOracleCipher) -> Self {
+        CompressionOracle {
+            session_id: session_id.to_vec(),
+            cipher,
+        }
+    }
+
+    pub fn format_request(&self, body: &[u8]) -> Vec<u8> {
+        [
+            b"POST / HTTP/1.1\nHost: hapless.com\nCookie: sessionid=".to_vec(),
+            self.session_id.clone(),
+            format!("\nContent-Length: {}\n", body.len()).into_bytes(),
+            body.to_vec(),
+        ]
+        .concat()
+    }
+
+    pub fn ciphertext_len(&self, body: &[u8]) -> usize {
+        // fresh key (and nonce or IV) every time, so only the length leaks anything
+        let compressed = deflate(&self.format_request(body));
+        let key = rand_bytes();
+        match self.cipher {
+            CompressionOracleCipher::Ctr => {
+                let nonce = u64::from_le_bytes(rand_bytes()[..8].try_into().unwrap());
+                aes_128_ctr(&compressed, &key, nonce).len()
+            }
+            CompressionOracleCipher::Cbc => {
+                encrypt_aes_128_cbc(&pkcs7_pad(compressed, 16), &key, &rand_bytes()).len()
+            }
+        }
+    }
+}
+
+// padding is drawn from the non-ASCII bytes, which never occur in a request or a base64
+// session id, so it compresses badly and only shifts the length of everything after it
+const CRIME_MAX_PADDING: usize = 128;
+// goes after the guess in one try and before it in the other
+const CRIME_TWO_TRIES: &[u8] = b"{}{}{}{}";
+// how many length steps are tried for every guess
+const CRIME_EDGES: usize = 32;
+// stands in for a wrong guess while looking for length steps
+const CRIME_WRONG_GUESS: u8 = 0x7f;
+
+fn crime_padding(len: usize) -> Vec<u8> {
+    (0x80..=0xffu8).take(len).collect()
+}
+
+pub fn crime_attack<O>(
+    mut ciphertext_len: O,
+    known_prefix: &[u8],
+    alphabet: &[u8],
+    terminator: u8,
+    max_len: usize,
+) -> Vec<u8>
+where
+    O: FnMut(&[u8]) -> usize,
+{
+    // guesses one byte at a time with the "two tries" trick: known_prefix || recovered ||
+    // guess || filler and known_prefix || recovered || filler || guess contain the same
+    // literals, so they only differ in length when the guess extends the match with the secret.
+    // That difference is a few bits and usually lost to rounding (to whole bytes, or whole
+    // blocks for CBC), so each round first finds the amounts of padding at which the ciphertext
+    // for a wrong guess has only just grown, where a few bits less falls back below the
+    // boundary. The guess whose first try comes out shortest over those paddings wins. Stops
+    // after recovering terminator or max_len bytes, returning what was recovered without the
+    // terminator.
+    let mut recovered: Vec<u8> = vec![];
+    let candidates: Vec<u8> = alphabet.iter().copied().chain([terminator]).collect();
+    while recovered.len() < max_len {
+        let base = [
+            known_prefix,
+            &recovered,
+            CRIME_TWO_TRIES,
+            &[CRIME_WRONG_GUESS],
+        ]
+        .concat();
+        let mut edges: Vec<Vec<u8>> = vec![];
+        let mut previous_len = ciphertext_len(&base);
+        for padding_len in 1..=CRIME_MAX_PADDING {
+            let padding = crime_padding(padding_len);
+            let len = ciphertext_len(&[&padding, &base[..]].concat());
+            if len > previous_len {
+                edges.push(padding);
+                if edges.len() == CRIME_EDGES {
+                    break;
+                }
+            }
+            previous_len = len;
+        }
+        let next = *candidates
+            .iter()
+            .min_by_key(|&&c| {
+                edges
+                    .iter()
+                    .map(|padding| {
+                        let guess_first =
+                            [padding, known_prefix, &recovered, &[c], CRIME_TWO_TRIES].concat();
+                        let guess_last =
+                            [padding, known_prefix, &recovered, CRIME_TWO_TRIES, &[c]].concat();
+                        ciphertext_len(&guess_first) as i64 - ciphertext_len(&guess_last) as i64
+                    })
+                    .sum::<i64>()
+            })
+            .unwrap();
+        if next == terminator {
+            break;
+        }
+        recovered.push(next);
+    }
+    recovered
+}
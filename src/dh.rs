@@ -0,0 +1,212 @@
+use crate::bignum::{biguint_to_bytes_padded, crt, random_biguint_range, small_prime_factors};
+use crate::kangaroo::{KangarooGroup, KangarooParams, kangaroo};
+use crate::shared_secret_mac;
+use num_bigint::BigUint;
+use num_integer::Integer;
+use num_traits::{One, Zero};
+use rand::Rng;
+
+// finite field Diffie-Hellman in a subgroup of prime order q of the integers mod p, and the
+// attacks on a party that does not check the other side's public key is in that subgroup
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DhParams {
+    pub p: BigUint,
+    pub g: BigUint,
+    pub q: BigUint,
+}
+
+impl DhParams {
+    pub fn new(p: BigUint, g: BigUint, q: BigUint) -> Self {
+        assert!(
+            ((&p - 1u32) % &q).is_zero(),
+            "q must divide the group order."
+        );
+        assert!(g.modpow(&q, &p).is_one(), "g must have order q.");
+        DhParams { p, g, q }
+    }
+
+    pub fn challenge_57() -> Self {
+        // (p - 1) / q has plenty of factors below 2^16, more than enough to cover q
+        DhParams::new(
+            parse_decimal(
+                "7199773997391911030609999317773941274322764333428698921736339643928346453700085358802973900485592910475480089726140708102474957429903531369589969318716771",
+            ),
+            parse_decimal(
+                "4565356397095740655436854503483826832136106141639563487732438195343690437606117828318042418238184896212352329118608100083187535033402010599512641674644143",
+            ),
+            parse_decimal("236234353446506858198510045061214171961"),
+        )
+    }
+
+    pub fn challenge_58() -> Self {
+        // the small factors of (p - 1) / q leave about 40 bits of a key for the kangaroo
+        DhParams::new(
+            parse_decimal(
+                "11470374874925275658116663507232161402086650258453896274534991676898999262641581519101074740642369848233294239851519212341844337347119899874391456329785623",
+            ),
+            parse_decimal(
+                "622952335333961296978159266084741085889881358738459939978290179936063635566740258555167783009058567397963466103140082647486611657350811560630587013183357",
+            ),
+            parse_decimal("335062023296420808191071248367701059461"),
+        )
+    }
+
+    pub fn cofactor(&self) -> BigUint {
+        // j = (p - 1) / q
+        (&self.p - 1u32) / &self.q
+    }
+
+    pub fn element_len(&self) -> usize {
+        self.p.bits().div_ceil(8) as usize
+    }
+}
+
+fn parse_decimal(s: &str) -> BigUint {
+    BigUint::parse_bytes(s.as_bytes(), 10).expect("Unable to parse decimal integer.")
+}
+
+// the whole multiplicative group mod p, not just the subgroup generated by g
+impl KangarooGroup for DhParams {
+    type Element = BigUint;
+
+    fn combine(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        a * b % &self.p
+    }
+
+    fn power(&self, a: &BigUint, k: &BigUint) -> BigUint {
+        a.modpow(k, &self.p)
+    }
+
+    fn jump_index(&self, a: &BigUint, jumps: u32) -> usize {
+        (a % jumps).try_into().unwrap()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DhPublicKey {
+    pub params: DhParams,
+    pub y: BigUint,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DhPrivateKey {
+    pub params: DhParams,
+    pub x: BigUint,
+}
+
+pub fn generate_dh_keypair<R: Rng + ?Sized>(
+    params: &DhParams,
+    rng: &mut R,
+) -> (DhPublicKey, DhPrivateKey) {
+    let x = random_biguint_range(&BigUint::one(), &params.q, rng);
+    let y = params.g.modpow(&x, &params.p);
+    (
+        DhPublicKey {
+            params: params.clone(),
+            y,
+        },
+        DhPrivateKey {
+            params: params.clone(),
+            x,
+        },
+    )
+}
+
+// Bob answers any public key with a MAC under the shared secret
+pub struct DhMacOracle {
+    private_key: DhPrivateKey,
+}
+
+impl DhMacOracle {
+    pub fn new(private_key: DhPrivateKey) -> Self {
+        DhMacOracle { private_key }
+    }
+
+    pub fn respond(&self, h: &BigUint) -> Vec<u8> {
+        // INSECURE: never checks that h^q = 1
+        let params = &self.private_key.params;
+        let shared = h.modpow(&self.private_key.x, &params.p);
+        shared_secret_mac(&biguint_to_bytes_padded(&shared, params.element_len()))
+    }
+}
+
+pub fn find_element_of_order<R: Rng + ?Sized>(params: &DhParams, r: u64, rng: &mut R) -> BigUint {
+    // r must be a prime dividing p - 1
+    assert!(((&params.p - 1u32) % r).is_zero(), "r must divide p - 1.");
+    let exponent = (&params.p - 1u32) / r;
+    loop {
+        let h =
+            random_biguint_range(&BigUint::from(2u32), &params.p, rng).modpow(&exponent, &params.p);
+        if !h.is_one() {
+            return h;
+        }
+    }
+}
+
+pub fn dh_subgroup_confinement_attack<F, R>(
+    params: &DhParams,
+    mut mac_oracle: F,
+    max_factor: u64,
+    rng: &mut R,
+) -> (BigUint, BigUint)
+where
+    F: FnMut(&BigUint) -> Vec<u8>,
+    R: Rng + ?Sized,
+{
+    // an element h of small prime order r confines Bob's shared secret to r possibilities, and
+    // the one matching his MAC gives x mod r. returns (x mod m, m) for m the product of the
+    // factors of j below max_factor, stopping early once m exceeds q
+    let mut residues = vec![];
+    let mut moduli: Vec<BigUint> = vec![];
+    for r in small_prime_factors(&params.cofactor(), max_factor) {
+        if moduli.iter().product::<BigUint>() > params.q {
+            continue;
+        }
+        let h = find_element_of_order(params, r, rng);
+        let mac = mac_oracle(&h);
+        let mut guess = BigUint::one();
+        for k in 0..r {
+            if shared_secret_mac(&biguint_to_bytes_padded(&guess, params.element_len())) == mac {
+                residues.push(k.into());
+                moduli.push(r.into());
+                break;
+            }
+            guess = guess * &h % &params.p;
+        }
+    }
+    crt(&residues, &moduli)
+}
+
+pub fn dh_kangaroo_attack<F, R>(
+    public_key: &DhPublicKey,
+    mac_oracle: F,
+    max_factor: u64,
+    rng: &mut R,
+) -> Option<BigUint>
+where
+    F: FnMut(&BigUint) -> Vec<u8>,
+    R: Rng + ?Sized,
+{
+    // with x = n mod m from the subgroup confinement attack, x = n + m * k for some k below
+    // q / m, and y * g^-n = (g^m)^k leaves only k for the kangaroo to find
+    let params = &public_key.params;
+    let (n, m) = dh_subgroup_confinement_attack(params, mac_oracle, max_factor, rng);
+    if m > params.q {
+        return Some(n % &params.q);
+    }
+    let g_m = params.g.modpow(&m, &params.p);
+    let g_inverse_n = params.g.modpow(&(&params.q - &n % &params.q), &params.p);
+    let shifted = &public_key.y * g_inverse_n % &params.p;
+    let upper = Integer::div_ceil(&params.q, &m);
+    let kangaroo_params = KangarooParams::for_width(&upper);
+    let k = kangaroo(
+        params,
+        &g_m,
+        &shifted,
+        &BigUint::zero(),
+        &upper,
+        &kangaroo_params,
+    )?;
+    Some((n + m * k) % &params.q)
+}
@@ -0,0 +1,229 @@
+use crate::bignum::{mod_sub, random_biguint_range};
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+use rand::Rng;
+use sha1::{Digest, Sha1};
+use std::collections::HashMap;
+
+// the domain parameters used throughout the cryptopals DSA challenges
+pub const DSA_P_HEX: &str = "800000000000000089e1855218a0e7dac38136ffafa72eda7859f2171e25e65eac698c1702578b07dc2a1076da241c76c62d374d8389ea5aeffd3226a0530cc565f3bf6b50929139ebeac04f48c3c84afb796d61e5a4f9a8fda812ab59494232c7d2b4deb50aa18ee9e132bfa85ac4374d7f9091abc3d015efc871a584471bb1";
+pub const DSA_Q_HEX: &str = "f4f47f05794b256174bba6e9b396a7707e563c5b";
+pub const DSA_G_HEX: &str = "5958c9d3898b224b12672c0b98e06c60df923cb8bc999d119458fef538b8fa4046c8db53039db620c094c9fa077ef389b5322a559946a71903f990f1f7e0e025e2d7f7cf494aff1a0470f5b64c36b625a097f1651fe775323556fe00b3608c887892878480e99041be601a62166ca6894bdd41a7054ec89f756ba9fc95302291";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DsaParams {
+    pub p: BigUint,
+    pub q: BigUint,
+    pub g: BigUint,
+}
+
+impl DsaParams {
+    pub fn challenge() -> Self {
+        DsaParams {
+            p: hex_to_biguint(DSA_P_HEX),
+            q: hex_to_biguint(DSA_Q_HEX),
+            g: hex_to_biguint(DSA_G_HEX),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DsaPublicKey {
+    pub params: DsaParams,
+    pub y: BigUint,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DsaPrivateKey {
+    pub params: DsaParams,
+    pub x: BigUint,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DsaSignature {
+    pub r: BigUint,
+    pub s: BigUint,
+}
+
+pub fn hex_to_biguint(hex_str: &str) -> BigUint {
+    BigUint::parse_bytes(hex_str.as_bytes(), 16).expect("Unable to parse hex integer.")
+}
+
+pub fn dsa_hash(message: &[u8]) -> BigUint {
+    BigUint::from_bytes_be(&Sha1::digest(message))
+}
+
+pub fn generate_dsa_keypair<R: Rng + ?Sized>(
+    params: &DsaParams,
+    rng: &mut R,
+) -> (DsaPublicKey, DsaPrivateKey) {
+    let x = random_biguint_range(&BigUint::one(), &params.q, rng);
+    let y = params.g.modpow(&x, &params.p);
+    (
+        DsaPublicKey {
+            params: params.clone(),
+            y,
+        },
+        DsaPrivateKey {
+            params: params.clone(),
+            x,
+        },
+    )
+}
+
+pub fn dsa_sign_with_nonce(
+    message: &[u8],
+    private_key: &DsaPrivateKey,
+    k: &BigUint,
+) -> Option<DsaSignature> {
+    // no check that r is non-zero, so tampered parameters still produce a signature
+    let DsaParams { p, q, g } = &private_key.params;
+    let r = g.modpow(k, p) % q;
+    let k_inv = k.modinv(q)?;
+    let s = (k_inv * (dsa_hash(message) + &private_key.x * &r)) % q;
+    if s.is_zero() {
+        return None;
+    }
+    Some(DsaSignature { r, s })
+}
+
+pub fn dsa_sign<R: Rng + ?Sized>(
+    message: &[u8],
+    private_key: &DsaPrivateKey,
+    rng: &mut R,
+) -> DsaSignature {
+    loop {
+        let k = random_biguint_range(&BigUint::one(), &private_key.params.q, rng);
+        if let Some(signature) = dsa_sign_with_nonce(message, private_key, &k) {
+            return signature;
+        }
+    }
+}
+
+fn dsa_verify_unchecked(
+    message: &[u8],
+    signature: &DsaSignature,
+    public_key: &DsaPublicKey,
+) -> bool {
+    let DsaParams { p, q, g } = &public_key.params;
+    let Some(w) = signature.s.modinv(q) else {
+        return false;
+    };
+    let u1 = (dsa_hash(message) * &w) % q;
+    let u2 = (&signature.r * &w) % q;
+    let v = ((g.modpow(&u1, p) * public_key.y.modpow(&u2, p)) % p) % q;
+    v == signature.r
+}
+
+pub fn dsa_verify(message: &[u8], signature: &DsaSignature, public_key: &DsaPublicKey) -> bool {
+    let q = &public_key.params.q;
+    let in_range = |v: &BigUint| !v.is_zero() && v < q;
+    in_range(&signature.r)
+        && in_range(&signature.s)
+        && dsa_verify_unchecked(message, signature, public_key)
+}
+
+pub fn dsa_verify_sloppy(
+    message: &[u8],
+    signature: &DsaSignature,
+    public_key: &DsaPublicKey,
+) -> bool {
+    // INSECURE: skips the 0 < r < q and 0 < s < q checks, so r = 0 passes under g = 0
+    dsa_verify_unchecked(message, signature, public_key)
+}
+
+pub fn dsa_x_from_nonce(
+    message_hash: &BigUint,
+    signature: &DsaSignature,
+    k: &BigUint,
+    q: &BigUint,
+) -> Option<BigUint> {
+    // x = (s * k - H(m)) / r mod q
+    let r_inv = signature.r.modinv(q)?;
+    Some((mod_sub(&(&signature.s * k), message_hash, q) * r_inv) % q)
+}
+
+pub fn dsa_brute_force_nonce(
+    message: &[u8],
+    signature: &DsaSignature,
+    public_key: &DsaPublicKey,
+    max_k: u64,
+) -> Option<BigUint> {
+    // tries every k in [1, max_k] against r, stepping g^k by one multiplication at a time,
+    // and returns the private key x
+    let DsaParams { p, q, g } = &public_key.params;
+    let mut g_k = BigUint::one();
+    for k in 1..=max_k {
+        g_k = (g_k * g) % p;
+        if &g_k % q != signature.r {
+            continue;
+        }
+        let k = BigUint::from(k);
+        if let Some(x) = dsa_x_from_nonce(&dsa_hash(message), signature, &k, q)
+            && g.modpow(&x, p) == public_key.y
+        {
+            return Some(x);
+        }
+    }
+    None
+}
+
+pub fn find_repeated_nonce(signatures: &[DsaSignature]) -> Option<(usize, usize)> {
+    // signatures made with the same k share r
+    let mut seen: HashMap<&BigUint, usize> = HashMap::new();
+    for (i, signature) in signatures.iter().enumerate() {
+        if let Some(&j) = seen.get(&signature.r) {
+            return Some((j, i));
+        }
+        seen.insert(&signature.r, i);
+    }
+    None
+}
+
+pub fn dsa_nonce_from_repeated_nonce(
+    m1: &BigUint,
+    s1: &BigUint,
+    m2: &BigUint,
+    s2: &BigUint,
+    q: &BigUint,
+) -> Option<BigUint> {
+    // k = (m1 - m2) / (s1 - s2) mod q
+    let denominator = mod_sub(s1, s2, q).modinv(q)?;
+    Some((mod_sub(m1, m2, q) * denominator) % q)
+}
+
+pub fn dsa_recover_x_from_repeated_nonce(
+    messages_signatures: &[(Vec<u8>, DsaSignature)],
+    public_key: &DsaPublicKey,
+) -> Option<BigUint> {
+    let DsaParams { p, q, g } = &public_key.params;
+    let signatures: Vec<DsaSignature> = messages_signatures
+        .iter()
+        .map(|(_, signature)| signature.clone())
+        .collect();
+    let (i, j) = find_repeated_nonce(&signatures)?;
+    let (m1, sig1) = &messages_signatures[i];
+    let (m2, sig2) = &messages_signatures[j];
+    let m1 = dsa_hash(m1);
+    let k = dsa_nonce_from_repeated_nonce(&m1, &sig1.s, &dsa_hash(m2), &sig2.s, q)?;
+    let x = dsa_x_from_nonce(&m1, sig1, &k, q)?;
+    (g.modpow(&x, p) == public_key.y).then_some(x)
+}
+
+pub fn dsa_magic_signature_g_zero() -> DsaSignature {
+    // under g = 0 every v is 0, so r = 0 with any invertible s verifies for every message
+    // (against a verifier that does not range check r)
+    DsaSignature {
+        r: BigUint::zero(),
+        s: BigUint::one(),
+    }
+}
+
+pub fn dsa_magic_signature_g_p_plus_one(public_key: &DsaPublicKey, z: &BigUint) -> DsaSignature {
+    // under g = p + 1 every power of g is 1 mod p, so v = y^(r / s) mod p mod q and
+    // r = y^z, s = r / z verifies for every message
+    let DsaParams { p, q, .. } = &public_key.params;
+    let r = public_key.y.modpow(z, p) % q;
+    let s = (&r * z.modinv(q).expect("z must be invertible mod q.")) % q;
+    DsaSignature { r, s }
+}
@@ -0,0 +1,954 @@
+use crate::bignum::{
+    biguint_to_bytes_padded, crt, mod_sqrt, mod_sub, random_biguint_below, random_biguint_range,
+    small_prime_factors,
+};
+use crate::dsa::hex_to_biguint;
+use crate::kangaroo::{KangarooGroup, KangarooParams, kangaroo};
+use crate::lll::lll;
+use crate::shared_secret_mac;
+use num_bigint::{BigInt, BigUint};
+use num_integer::Integer;
+use num_rational::BigRational;
+use num_traits::{One, Signed, Zero};
+use rand::Rng;
+use sha2::{Digest, Sha256};
+
+// elliptic curves over prime fields: short Weierstrass curves y^2 = x^3 + ax + b with affine and
+// Jacobian projective arithmetic, and x-only Montgomery curves Bv^2 = u^3 + Au^2 + u
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EcPoint {
+    Infinity,
+    Affine(BigUint, BigUint),
+}
+
+// (X, Y, Z) stands for the affine point (X / Z^2, Y / Z^3), and Z = 0 for the point at infinity.
+// adding and doubling need no inversions, so scalar multiplication only inverts once at the end
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProjectivePoint {
+    pub x: BigUint,
+    pub y: BigUint,
+    pub z: BigUint,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WeierstrassCurve {
+    pub p: BigUint,
+    pub a: BigUint,
+    pub b: BigUint,
+}
+
+impl WeierstrassCurve {
+    pub fn new(p: BigUint, a: BigUint, b: BigUint) -> Self {
+        // 4a^3 + 27b^2 must not vanish or the curve is singular
+        let discriminant = (BigUint::from(4u32) * a.modpow(&BigUint::from(3u32), &p)
+            + BigUint::from(27u32) * &b * &b)
+            % &p;
+        assert!(!discriminant.is_zero(), "Singular curve.");
+        WeierstrassCurve { p, a, b }
+    }
+
+    fn inverse(&self, v: &BigUint) -> BigUint {
+        v.modinv(&self.p).expect("Division by zero.")
+    }
+
+    fn sub(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        mod_sub(a, b, &self.p)
+    }
+
+    pub fn rhs(&self, x: &BigUint) -> BigUint {
+        // x^3 + ax + b
+        (x * x * x + &self.a * x + &self.b) % &self.p
+    }
+
+    pub fn is_on_curve(&self, point: &EcPoint) -> bool {
+        match point {
+            EcPoint::Infinity => true,
+            EcPoint::Affine(x, y) => x < &self.p && y < &self.p && y * y % &self.p == self.rhs(x),
+        }
+    }
+
+    pub fn negate(&self, point: &EcPoint) -> EcPoint {
+        match point {
+            EcPoint::Infinity => EcPoint::Infinity,
+            EcPoint::Affine(x, y) => EcPoint::Affine(x.clone(), (&self.p - y) % &self.p),
+        }
+    }
+
+    pub fn add(&self, p1: &EcPoint, p2: &EcPoint) -> EcPoint {
+        // b never appears, so points from any curve with the same a and p add without complaint
+        let (EcPoint::Affine(x1, y1), EcPoint::Affine(x2, y2)) = (p1, p2) else {
+            return if p1 == &EcPoint::Infinity {
+                p2.clone()
+            } else {
+                p1.clone()
+            };
+        };
+        let p = &self.p;
+        let slope = if x1 == x2 {
+            if ((y1 + y2) % p).is_zero() {
+                return EcPoint::Infinity;
+            }
+            (BigUint::from(3u32) * x1 * x1 + &self.a) * self.inverse(&(y1 << 1u32)) % p
+        } else {
+            self.sub(y2, y1) * self.inverse(&self.sub(x2, x1)) % p
+        };
+        let x3 = self.sub(&(&slope * &slope), &(x1 + x2));
+        let y3 = self.sub(&(slope * self.sub(x1, &x3)), y1);
+        EcPoint::Affine(x3, y3)
+    }
+
+    pub fn double(&self, point: &EcPoint) -> EcPoint {
+        self.add(point, point)
+    }
+
+    pub fn scalar_mul(&self, point: &EcPoint, k: &BigUint) -> EcPoint {
+        // left to right double and add in affine coordinates
+        let mut result = EcPoint::Infinity;
+        for i in (0..k.bits()).rev() {
+            result = self.double(&result);
+            if k.bit(i) {
+                result = self.add(&result, point);
+            }
+        }
+        result
+    }
+
+    pub fn lift_x(&self, x: &BigUint) -> Option<EcPoint> {
+        // the point with this x and the smaller y, if there is one
+        let x = x % &self.p;
+        let y = mod_sqrt(&self.rhs(&x), &self.p)?;
+        Some(EcPoint::Affine(x, y))
+    }
+
+    pub fn random_point<R: Rng + ?Sized>(&self, rng: &mut R) -> EcPoint {
+        loop {
+            let x = random_biguint_below(&self.p, rng);
+            if let Some(point) = self.lift_x(&x) {
+                return if rng.random() {
+                    self.negate(&point)
+                } else {
+                    point
+                };
+            }
+        }
+    }
+
+    pub fn to_projective(&self, point: &EcPoint) -> ProjectivePoint {
+        match point {
+            EcPoint::Infinity => ProjectivePoint {
+                x: BigUint::one(),
+                y: BigUint::one(),
+                z: BigUint::zero(),
+            },
+            EcPoint::Affine(x, y) => ProjectivePoint {
+                x: x.clone(),
+                y: y.clone(),
+                z: BigUint::one(),
+            },
+        }
+    }
+
+    pub fn to_affine(&self, point: &ProjectivePoint) -> EcPoint {
+        if point.z.is_zero() {
+            return EcPoint::Infinity;
+        }
+        let p = &self.p;
+        let z_inv = self.inverse(&point.z);
+        let z_inv2 = &z_inv * &z_inv % p;
+        let x = &point.x * &z_inv2 % p;
+        let y = &point.y * z_inv2 * z_inv % p;
+        EcPoint::Affine(x, y)
+    }
+
+    pub fn double_projective(&self, point: &ProjectivePoint) -> ProjectivePoint {
+        let p = &self.p;
+        let ProjectivePoint { x, y, z } = point;
+        if z.is_zero() || y.is_zero() {
+            return self.to_projective(&EcPoint::Infinity);
+        }
+        // s = 4xy^2, m = 3x^2 + az^4, x' = m^2 - 2s, y' = m(s - x') - 8y^4, z' = 2yz
+        let y2 = y * y % p;
+        let s = BigUint::from(4u32) * x * &y2 % p;
+        let z2 = z * z % p;
+        let m = (BigUint::from(3u32) * x * x + &self.a * &z2 * &z2) % p;
+        let x3 = self.sub(&(&m * &m), &(&s << 1u32));
+        let y3 = self.sub(&(m * self.sub(&s, &x3)), &(BigUint::from(8u32) * &y2 * &y2));
+        let z3 = ((y * z) << 1u32) % p;
+        ProjectivePoint {
+            x: x3,
+            y: y3,
+            z: z3,
+        }
+    }
+
+    pub fn add_projective(&self, p1: &ProjectivePoint, p2: &ProjectivePoint) -> ProjectivePoint {
+        let p = &self.p;
+        if p1.z.is_zero() {
+            return p2.clone();
+        }
+        if p2.z.is_zero() {
+            return p1.clone();
+        }
+        let z1_2 = &p1.z * &p1.z % p;
+        let z2_2 = &p2.z * &p2.z % p;
+        let u1 = &p1.x * &z2_2 % p;
+        let u2 = &p2.x * &z1_2 % p;
+        let s1 = &p1.y * z2_2 * &p2.z % p;
+        let s2 = &p2.y * z1_2 * &p1.z % p;
+        if u1 == u2 {
+            return if s1 == s2 {
+                self.double_projective(p1)
+            } else {
+                self.to_projective(&EcPoint::Infinity)
+            };
+        }
+        let h = self.sub(&u2, &u1);
+        let r = self.sub(&s2, &s1);
+        let h2 = &h * &h % p;
+        let h3 = &h2 * &h % p;
+        let u1h2 = u1 * h2 % p;
+        let x3 = self.sub(&(&r * &r), &((&h3 + (&u1h2 << 1u32)) % p));
+        let y3 = self.sub(&(r * self.sub(&u1h2, &x3)), &(s1 * h3));
+        let z3 = h * &p1.z * &p2.z % p;
+        ProjectivePoint {
+            x: x3,
+            y: y3,
+            z: z3,
+        }
+    }
+
+    pub fn scalar_mul_projective(&self, point: &EcPoint, k: &BigUint) -> EcPoint {
+        let base = self.to_projective(point);
+        let mut result = self.to_projective(&EcPoint::Infinity);
+        for i in (0..k.bits()).rev() {
+            result = self.double_projective(&result);
+            if k.bit(i) {
+                result = self.add_projective(&result, &base);
+            }
+        }
+        self.to_affine(&result)
+    }
+
+    pub fn field_len(&self) -> usize {
+        self.p.bits().div_ceil(8) as usize
+    }
+
+    pub fn encode_point(&self, point: &EcPoint, compressed: bool) -> Vec<u8> {
+        // SEC 1: 0x00 for infinity, 0x04 || x || y, or 0x02 / 0x03 (by the parity of y) || x
+        let len = self.field_len();
+        match point {
+            EcPoint::Infinity => vec![0],
+            EcPoint::Affine(x, y) if compressed => {
+                [vec![2 + y.bit(0) as u8], biguint_to_bytes_padded(x, len)].concat()
+            }
+            EcPoint::Affine(x, y) => [
+                vec![4],
+                biguint_to_bytes_padded(x, len),
+                biguint_to_bytes_padded(y, len),
+            ]
+            .concat(),
+        }
+    }
+
+    pub fn decode_point(&self, bytes: &[u8]) -> Option<EcPoint> {
+        // None unless the encoding is well formed and the point is on this curve
+        let len = self.field_len();
+        let point = match (bytes.first()?, bytes.len()) {
+            (0, 1) => EcPoint::Infinity,
+            (4, l) if l == 1 + 2 * len => EcPoint::Affine(
+                BigUint::from_bytes_be(&bytes[1..1 + len]),
+                BigUint::from_bytes_be(&bytes[1 + len..]),
+            ),
+            (&tag @ (2 | 3), l) if l == 1 + len => {
+                let x = BigUint::from_bytes_be(&bytes[1..]);
+                if x >= self.p {
+                    return None;
+                }
+                let point = self.lift_x(&x)?;
+                match point {
+                    EcPoint::Affine(_, ref y) if y.bit(0) != (tag == 3) => self.negate(&point),
+                    _ => point,
+                }
+            }
+            _ => return None,
+        };
+        self.is_on_curve(&point).then_some(point)
+    }
+}
+
+// a Montgomery curve only needs u coordinates: the ladder computes u(kP) from u(P) and never looks
+// at B, which makes it quietly work on the quadratic twist too
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MontgomeryCurve {
+    pub p: BigUint,
+    pub a: BigUint,
+    pub b: BigUint,
+}
+
+impl MontgomeryCurve {
+    pub fn ladder(&self, u: &BigUint, k: &BigUint) -> BigUint {
+        // the point at infinity comes out as 0, as in the cryptopals ladder
+        let p = &self.p;
+        let sub = |a: &BigUint, b: &BigUint| mod_sub(a, b, p);
+        let (mut u2, mut w2) = (BigUint::one(), BigUint::zero());
+        let (mut u3, mut w3) = (u % p, BigUint::one());
+        for i in (0..p.bits()).rev() {
+            let bit = k.bit(i);
+            if bit {
+                std::mem::swap(&mut u2, &mut u3);
+                std::mem::swap(&mut w2, &mut w3);
+            }
+            let t = sub(&(&u2 * &u3), &(&w2 * &w3));
+            let v = sub(&(&u2 * &w3), &(&w2 * &u3));
+            (u3, w3) = (&t * &t % p, u * &v * &v % p);
+            let t = sub(&(&u2 * &u2), &(&w2 * &w2));
+            let v = BigUint::from(4u32) * &u2 * &w2 * (&u2 * &u2 + &self.a * &u2 * &w2 + &w2 * &w2);
+            (u2, w2) = (&t * &t % p, v % p);
+            if bit {
+                std::mem::swap(&mut u2, &mut u3);
+                std::mem::swap(&mut w2, &mut w3);
+            }
+        }
+        let w_inv = w2.modpow(&(p - 2u32), p);
+        u2 * w_inv % p
+    }
+
+    pub fn field_len(&self) -> usize {
+        self.p.bits().div_ceil(8) as usize
+    }
+
+    pub fn is_on_twist(&self, u: &BigUint) -> bool {
+        // u^3 + Au^2 + u is not a square, so there is no v on the curve
+        let p = &self.p;
+        let rhs = (u * u * u + &self.a * u * u + u) % p;
+        !rhs.is_zero() && !rhs.modpow(&((p - 1u32) >> 1), p).is_one()
+    }
+
+    pub fn to_weierstrass(&self) -> WeierstrassCurve {
+        // a = (3 - A^2) / 3B^2 and b = (2A^3 - 9A) / 27B^3
+        let p = &self.p;
+        let inv = |v: BigUint| (v % p).modinv(p).expect("Division by zero.");
+        let a2 = &self.a * &self.a % p;
+        let b2 = &self.b * &self.b % p;
+        let a = mod_sub(&BigUint::from(3u32), &a2, p) * inv(BigUint::from(3u32) * &b2) % p;
+        let b = mod_sub(
+            &(BigUint::from(2u32) * &a2 * &self.a),
+            &(BigUint::from(9u32) * &self.a),
+            p,
+        ) * inv(BigUint::from(27u32) * b2 * &self.b)
+            % p;
+        WeierstrassCurve::new(p.clone(), a, b)
+    }
+
+    pub fn u_to_weierstrass_x(&self, u: &BigUint) -> BigUint {
+        // x = (3u + A) / 3B
+        let p = &self.p;
+        let denominator = (BigUint::from(3u32) * &self.b % p).modinv(p).unwrap();
+        (BigUint::from(3u32) * u + &self.a) * denominator % p
+    }
+
+    pub fn weierstrass_x_to_u(&self, x: &BigUint) -> BigUint {
+        // u = (3Bx - A) / 3
+        let p = &self.p;
+        let three_inv = BigUint::from(3u32).modinv(p).unwrap();
+        mod_sub(&(BigUint::from(3u32) * &self.b * x), &self.a, p) * three_inv % p
+    }
+}
+
+// a curve with a base point g of prime order n, and the cofactor h of that subgroup
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EcGroup {
+    pub curve: WeierstrassCurve,
+    pub g: EcPoint,
+    pub n: BigUint,
+    pub h: BigUint,
+}
+
+impl EcGroup {
+    pub fn cryptopals() -> Self {
+        // y^2 = x^3 - 95051x + 11279326 from challenge 59
+        let p = BigUint::parse_bytes(b"233970423115425145524320034830162017933", 10).unwrap();
+        let a = &p - 95051u32;
+        let g = EcPoint::Affine(
+            BigUint::from(182u32),
+            BigUint::parse_bytes(b"85518893674295321206118380980485522083", 10).unwrap(),
+        );
+        EcGroup {
+            curve: WeierstrassCurve::new(p, a, BigUint::from(11279326u32)),
+            g,
+            n: BigUint::parse_bytes(b"29246302889428143187362802287225875743", 10).unwrap(),
+            h: BigUint::from(8u32),
+        }
+    }
+
+    pub fn p256() -> Self {
+        let p = hex_to_biguint("ffffffff00000001000000000000000000000000ffffffffffffffffffffffff");
+        let a = &p - 3u32;
+        let b = hex_to_biguint("5ac635d8aa3a93e7b3ebbd55769886bc651d06b0cc53b0f63bce3c3e27d2604b");
+        let g = EcPoint::Affine(
+            hex_to_biguint("6b17d1f2e12c4247f8bce6e563a440f277037d812deb33a0f4a13945d898c296"),
+            hex_to_biguint("4fe342e2fe1a7f9b8ee7eb4a7c0f9e162bce33576b315ececbb6406837bf51f5"),
+        );
+        EcGroup {
+            curve: WeierstrassCurve::new(p, a, b),
+            g,
+            n: hex_to_biguint("ffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc632551"),
+            h: BigUint::one(),
+        }
+    }
+
+    pub fn order(&self) -> BigUint {
+        &self.n * &self.h
+    }
+}
+
+// the same group as EcGroup::cryptopals, as the Montgomery curve v^2 = u^3 + 534u^2 + u from
+// challenge 60
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MontgomeryGroup {
+    pub curve: MontgomeryCurve,
+    pub u: BigUint,
+    pub n: BigUint,
+    pub h: BigUint,
+}
+
+impl MontgomeryGroup {
+    pub fn cryptopals() -> Self {
+        let group = EcGroup::cryptopals();
+        MontgomeryGroup {
+            curve: MontgomeryCurve {
+                p: group.curve.p,
+                a: BigUint::from(534u32),
+                b: BigUint::one(),
+            },
+            u: BigUint::from(4u32),
+            n: group.n,
+            h: group.h,
+        }
+    }
+
+    pub fn twist_order(&self) -> BigUint {
+        // the curve and its twist have 2p + 2 points between them
+        (&self.curve.p << 1u32) + 2u32 - &self.n * &self.h
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EcPublicKey {
+    pub group: EcGroup,
+    pub q: EcPoint,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EcPrivateKey {
+    pub group: EcGroup,
+    pub d: BigUint,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EcdsaSignature {
+    pub r: BigUint,
+    pub s: BigUint,
+}
+
+pub fn generate_ec_keypair<R: Rng + ?Sized>(
+    group: &EcGroup,
+    rng: &mut R,
+) -> (EcPublicKey, EcPrivateKey) {
+    let d = random_biguint_range(&BigUint::one(), &group.n, rng);
+    let q = group.curve.scalar_mul_projective(&group.g, &d);
+    (
+        EcPublicKey {
+            group: group.clone(),
+            q,
+        },
+        EcPrivateKey {
+            group: group.clone(),
+            d,
+        },
+    )
+}
+
+pub fn ecdh_shared_secret(private_key: &EcPrivateKey, peer: &EcPoint) -> Option<Vec<u8>> {
+    // the x coordinate of d * peer, after checking the peer's point is on the curve and in the
+    // prime order subgroup
+    let EcGroup { curve, n, .. } = &private_key.group;
+    if peer == &EcPoint::Infinity
+        || !curve.is_on_curve(peer)
+        || curve.scalar_mul_projective(peer, n) != EcPoint::Infinity
+    {
+        return None;
+    }
+    match curve.scalar_mul_projective(peer, &private_key.d) {
+        EcPoint::Affine(x, _) => Some(biguint_to_bytes_padded(&x, curve.field_len())),
+        EcPoint::Infinity => None,
+    }
+}
+
+pub fn ecdsa_hash(message: &[u8], n: &BigUint) -> BigUint {
+    // SHA-256, keeping only the leftmost bits if n is shorter than the digest
+    let e = BigUint::from_bytes_be(&Sha256::digest(message));
+    e >> 256u64.saturating_sub(n.bits())
+}
+
+pub fn ecdsa_sign_with_nonce(
+    message: &[u8],
+    private_key: &EcPrivateKey,
+    k: &BigUint,
+) -> Option<EcdsaSignature> {
+    let EcGroup { curve, g, n, .. } = &private_key.group;
+    let EcPoint::Affine(x, _) = curve.scalar_mul_projective(g, k) else {
+        return None;
+    };
+    let r = x % n;
+    let k_inv = k.modinv(n)?;
+    let s = k_inv * (ecdsa_hash(message, n) + &private_key.d * &r) % n;
+    if r.is_zero() || s.is_zero() {
+        return None;
+    }
+    Some(EcdsaSignature { r, s })
+}
+
+pub fn ecdsa_sign<R: Rng + ?Sized>(
+    message: &[u8],
+    private_key: &EcPrivateKey,
+    rng: &mut R,
+) -> EcdsaSignature {
+    loop {
+        let k = random_biguint_range(&BigUint::one(), &private_key.group.n, rng);
+        if let Some(signature) = ecdsa_sign_with_nonce(message, private_key, &k) {
+            return signature;
+        }
+    }
+}
+
+pub fn ecdsa_verify(message: &[u8], signature: &EcdsaSignature, public_key: &EcPublicKey) -> bool {
+    let EcGroup { curve, g, n, .. } = &public_key.group;
+    let in_range = |v: &BigUint| !v.is_zero() && v < n;
+    if !in_range(&signature.r) || !in_range(&signature.s) {
+        return false;
+    }
+    let Some(w) = signature.s.modinv(n) else {
+        return false;
+    };
+    let u1 = ecdsa_hash(message, n) * &w % n;
+    let u2 = &signature.r * w % n;
+    let point = curve.add(
+        &curve.scalar_mul_projective(g, &u1),
+        &curve.scalar_mul_projective(&public_key.q, &u2),
+    );
+    match point {
+        EcPoint::Affine(x, _) => x % n == signature.r,
+        EcPoint::Infinity => false,
+    }
+}
+
+// Bob answers any point with a MAC under the shared secret, which is enough of an oracle for the
+// invalid curve attack
+pub struct EcdhMacOracle {
+    private_key: EcPrivateKey,
+}
+
+impl EcdhMacOracle {
+    pub fn new(private_key: EcPrivateKey) -> Self {
+        EcdhMacOracle { private_key }
+    }
+
+    pub fn respond(&self, peer: &EcPoint) -> Vec<u8> {
+        // INSECURE: never checks the point is on the curve, and the addition formulas never look
+        // at b
+        let curve = &self.private_key.group.curve;
+        let shared = curve.scalar_mul_projective(peer, &self.private_key.d);
+        shared_secret_mac(&curve.encode_point(&shared, false))
+    }
+}
+
+pub fn cryptopals_invalid_curves() -> Vec<(WeierstrassCurve, BigUint)> {
+    // (curve, order) for the curves of challenge 59, which share p and a with EcGroup::cryptopals
+    // but have orders with plenty of small factors
+    let group = EcGroup::cryptopals();
+    [
+        (210u32, "233970423115425145550826547352470124412"),
+        (504, "233970423115425145544350131142039591210"),
+        (727, "233970423115425145545378039958152057148"),
+    ]
+    .iter()
+    .map(|&(b, order)| {
+        let curve = WeierstrassCurve::new(group.curve.p.clone(), group.curve.a.clone(), b.into());
+        (curve, BigUint::parse_bytes(order.as_bytes(), 10).unwrap())
+    })
+    .collect()
+}
+
+pub fn find_point_of_order<R: Rng + ?Sized>(
+    curve: &WeierstrassCurve,
+    curve_order: &BigUint,
+    r: u64,
+    rng: &mut R,
+) -> EcPoint {
+    // r must be a prime dividing the curve order. the r-part of the group need not be cyclic, so
+    // clear the cofactor of the whole r-power and then multiply by r until one more step would
+    // reach infinity
+    assert!(
+        (curve_order % r).is_zero(),
+        "r must divide the curve order."
+    );
+    let mut cofactor = curve_order.clone();
+    while (&cofactor % r).is_zero() {
+        cofactor /= r;
+    }
+    loop {
+        let mut point = curve.scalar_mul_projective(&curve.random_point(rng), &cofactor);
+        if point == EcPoint::Infinity {
+            continue;
+        }
+        loop {
+            let next = curve.scalar_mul_projective(&point, &r.into());
+            if next == EcPoint::Infinity {
+                return point;
+            }
+            point = next;
+        }
+    }
+}
+
+pub fn ecdh_invalid_curve_attack<F, R>(
+    group: &EcGroup,
+    weak_curves: &[(WeierstrassCurve, BigUint)],
+    mut mac_oracle: F,
+    max_factor: u64,
+    rng: &mut R,
+) -> Option<BigUint>
+where
+    F: FnMut(&EcPoint) -> Vec<u8>,
+    R: Rng + ?Sized,
+{
+    // a point of small prime order r on a weak curve confines Bob's answer to r possibilities,
+    // and finding the one that matches his MAC gives d mod r. enough residues pin d down by CRT,
+    // or None if the weak curves do not have enough small factors
+    let mut residues = vec![];
+    let mut moduli: Vec<BigUint> = vec![];
+    for (curve, order) in weak_curves {
+        for r in small_prime_factors(order, max_factor) {
+            if moduli.contains(&r.into()) || moduli.iter().product::<BigUint>() > group.n {
+                continue;
+            }
+            let point = find_point_of_order(curve, order, r, rng);
+            let mac = mac_oracle(&point);
+            let mut guess = EcPoint::Infinity;
+            for k in 0..r {
+                if shared_secret_mac(&curve.encode_point(&guess, false)) == mac {
+                    residues.push(k.into());
+                    moduli.push(r.into());
+                    break;
+                }
+                guess = curve.add(&guess, &point);
+            }
+        }
+    }
+    if moduli.iter().product::<BigUint>() <= group.n {
+        return None;
+    }
+    let (d, _) = crt(&residues, &moduli);
+    Some(d % &group.n)
+}
+
+impl KangarooGroup for WeierstrassCurve {
+    type Element = EcPoint;
+
+    fn combine(&self, a: &EcPoint, b: &EcPoint) -> EcPoint {
+        self.add(a, b)
+    }
+
+    fn power(&self, a: &EcPoint, k: &BigUint) -> EcPoint {
+        self.scalar_mul_projective(a, k)
+    }
+
+    fn jump_index(&self, a: &EcPoint, jumps: u32) -> usize {
+        match a {
+            EcPoint::Affine(x, _) => (x % jumps).try_into().unwrap_or(0),
+            EcPoint::Infinity => 0,
+        }
+    }
+}
+
+pub fn ec_kangaroo(
+    curve: &WeierstrassCurve,
+    g: &EcPoint,
+    y: &EcPoint,
+    bound: &BigUint,
+) -> Option<BigUint> {
+    // the k in [0, bound) with y = kg, in about 2 sqrt(bound) additions
+    let params = KangarooParams::for_width(bound);
+    kangaroo(curve, g, y, &BigUint::zero(), bound, &params)
+}
+
+// Bob again, on the Montgomery curve, using the ladder on whatever u he is sent
+pub struct MontgomeryMacOracle {
+    group: MontgomeryGroup,
+    d: BigUint,
+}
+
+impl MontgomeryMacOracle {
+    pub fn new(group: MontgomeryGroup, d: BigUint) -> Self {
+        MontgomeryMacOracle { group, d }
+    }
+
+    pub fn public_u(&self) -> BigUint {
+        self.group.curve.ladder(&self.group.u, &self.d)
+    }
+
+    pub fn respond(&self, u: &BigUint) -> Vec<u8> {
+        // INSECURE: never checks u is on the curve rather than its twist
+        let curve = &self.group.curve;
+        let shared = curve.ladder(u, &self.d);
+        shared_secret_mac(&biguint_to_bytes_padded(&shared, curve.field_len()))
+    }
+}
+
+pub fn find_twist_point_of_order<R: Rng + ?Sized>(
+    curve: &MontgomeryCurve,
+    twist_order: &BigUint,
+    order: &BigUint,
+    order_factors: &[u64],
+    rng: &mut R,
+) -> BigUint {
+    // the u of a twist point of exactly this order, a squarefree product of order_factors
+    loop {
+        let u = random_biguint_below(&curve.p, rng);
+        if !curve.is_on_twist(&u) {
+            continue;
+        }
+        let candidate = curve.ladder(&u, &(twist_order / order));
+        if order_factors
+            .iter()
+            .all(|&q| !curve.ladder(&candidate, &(order / q)).is_zero())
+        {
+            return candidate;
+        }
+    }
+}
+
+fn montgomery_residue_from_mac(
+    curve: &MontgomeryCurve,
+    u: &BigUint,
+    r: u64,
+    mac: &[u8],
+) -> Option<u64> {
+    // the k in [0, r / 2] with u(kP) giving this MAC, walking the multiples of P with x-only
+    // differential additions: P_(k+1) = P_k + P_1, whose difference P_(k-1) is already known
+    let p = &curve.p;
+    let len = curve.field_len();
+    let sub = |a: &BigUint, b: &BigUint| mod_sub(a, b, p);
+    let matches = |uk: &BigUint, wk: &BigUint| {
+        let shared = match wk.modinv(p) {
+            Some(inv) => uk * inv % p,
+            None => BigUint::zero(),
+        };
+        shared_secret_mac(&biguint_to_bytes_padded(&shared, len)) == mac
+    };
+    if matches(&BigUint::one(), &BigUint::zero()) {
+        return Some(0);
+    }
+    let (mut previous_u, mut previous_w) = (BigUint::one(), BigUint::zero());
+    let (mut uk, mut wk) = (u.clone(), BigUint::one());
+    for k in 1..=r / 2 {
+        if matches(&uk, &wk) {
+            return Some(k);
+        }
+        let (next_u, next_w) = if k == 1 {
+            // P_2 by doubling, since the difference P_0 is the point at infinity
+            let t = sub(&(u * u), &BigUint::one());
+            (
+                &t * &t % p,
+                BigUint::from(4u32) * u * (u * u + &curve.a * u + 1u32) % p,
+            )
+        } else {
+            let t = sub(&(&uk * u), &wk);
+            let v = sub(&uk, &(&wk * u));
+            (&previous_w * &t * &t % p, &previous_u * &v * &v % p)
+        };
+        (previous_u, previous_w) = (uk, wk);
+        (uk, wk) = (next_u, next_w);
+    }
+    None
+}
+
+pub fn ecdh_twist_attack<F, R>(
+    group: &MontgomeryGroup,
+    public_u: &BigUint,
+    mut mac_oracle: F,
+    max_factor: u64,
+    d_bound: &BigUint,
+    rng: &mut R,
+) -> Option<BigUint>
+where
+    F: FnMut(&BigUint) -> Vec<u8>,
+    R: Rng + ?Sized,
+{
+    // u coordinates on the twist go through the ladder just as well, so small subgroups of the
+    // twist leak d mod r, though only up to sign since u(kP) = u(-kP). each new residue is
+    // merged with the ones so far by asking about a point whose order is the product of all
+    // the moduli, which leaves a single overall sign. the rest of d, below d_bound, is found with
+    // the kangaroo on the curve itself
+    let curve = &group.curve;
+    let twist_order = group.twist_order();
+    let factors: Vec<u64> = small_prime_factors(&twist_order, max_factor)
+        .into_iter()
+        .filter(|&r| r > 2)
+        .collect();
+    let mut x = BigUint::zero();
+    let mut modulus = BigUint::one();
+    let mut used: Vec<u64> = vec![];
+    for &r in &factors {
+        let point = find_twist_point_of_order(curve, &twist_order, &r.into(), &[r], rng);
+        let k = montgomery_residue_from_mac(curve, &point, r, &mac_oracle(&point))?;
+        used.push(r);
+        if modulus.is_one() {
+            (x, modulus) = (k.into(), r.into());
+            continue;
+        }
+        let order = &modulus * r;
+        let (plus, _) = crt(&[x.clone(), k.into()], &[modulus.clone(), r.into()]);
+        let (minus, _) = crt(
+            &[x.clone(), ((r - k) % r).into()],
+            &[modulus.clone(), r.into()],
+        );
+        let point = find_twist_point_of_order(curve, &twist_order, &order, &used, rng);
+        let mac = mac_oracle(&point);
+        let shared = curve.ladder(&point, &plus);
+        x = if shared_secret_mac(&biguint_to_bytes_padded(&shared, curve.field_len())) == mac {
+            plus
+        } else {
+            minus
+        };
+        modulus = order;
+    }
+    // d = +-x mod modulus, and the public key lifts to +-dG
+    let weierstrass = curve.to_weierstrass();
+    let g = weierstrass.lift_x(&curve.u_to_weierstrass_x(&group.u))?;
+    let y = weierstrass.lift_x(&curve.u_to_weierstrass_x(public_u))?;
+    let step = weierstrass.scalar_mul_projective(&g, &modulus);
+    let bound = Integer::div_ceil(d_bound, &modulus);
+    for residue in [x.clone(), (&modulus - &x) % &modulus] {
+        for target in [y.clone(), weierstrass.negate(&y)] {
+            let shifted = weierstrass.add(
+                &target,
+                &weierstrass.negate(&weierstrass.scalar_mul_projective(&g, &residue)),
+            );
+            if let Some(m) = ec_kangaroo(&weierstrass, &step, &shifted, &bound) {
+                let d = &residue + m * &modulus;
+                if &curve.ladder(&group.u, &d) == public_u {
+                    return Some(d);
+                }
+            }
+        }
+    }
+    None
+}
+
+pub fn ecdsa_dsks<R: Rng + ?Sized>(
+    message: &[u8],
+    signature: &EcdsaSignature,
+    public_key: &EcPublicKey,
+    rng: &mut R,
+) -> (EcPublicKey, EcPrivateKey) {
+    // duplicate signature key selection: ECDSA verification only checks that R = u1 * G + u2 * Q
+    // has x coordinate r, and nothing ties the key to a particular generator. for any d' the
+    // generator G' = (u1 + u2 * d')^-1 * R and Q' = d' * G' give a new key pair under which the
+    // existing signature also verifies
+    let EcGroup { curve, g, n, .. } = &public_key.group;
+    let w = signature.s.modinv(n).expect("s must be invertible.");
+    let u1 = ecdsa_hash(message, n) * &w % n;
+    let u2 = &signature.r * w % n;
+    let r_point = curve.add(
+        &curve.scalar_mul_projective(g, &u1),
+        &curve.scalar_mul_projective(&public_key.q, &u2),
+    );
+    loop {
+        let d = random_biguint_range(&BigUint::one(), n, rng);
+        let Some(t_inv) = ((&u1 + &u2 * &d) % n).modinv(n) else {
+            continue;
+        };
+        let group = EcGroup {
+            g: curve.scalar_mul_projective(&r_point, &t_inv),
+            ..public_key.group.clone()
+        };
+        let q = curve.scalar_mul_projective(&group.g, &d);
+        return (
+            EcPublicKey {
+                group: group.clone(),
+                q,
+            },
+            EcPrivateKey { group, d },
+        );
+    }
+}
+
+pub fn ecdsa_sign_biased<R: Rng + ?Sized>(
+    message: &[u8],
+    private_key: &EcPrivateKey,
+    zero_bits: u32,
+    rng: &mut R,
+) -> EcdsaSignature {
+    // INSECURE: the low zero_bits bits of every nonce are zero
+    loop {
+        let k = random_biguint_range(&BigUint::one(), &private_key.group.n, rng) >> zero_bits
+            << zero_bits;
+        if let Some(signature) = ecdsa_sign_with_nonce(message, private_key, &k) {
+            return signature;
+        }
+    }
+}
+
+pub fn ecdsa_biased_nonce_attack(
+    signed: &[(Vec<u8>, EcdsaSignature)],
+    public_key: &EcPublicKey,
+    zero_bits: u32,
+) -> Option<BigUint> {
+    // with k = 2^l * b, s = (H + d * r) / k rearranges to b = d * t - u mod n for
+    // t = r / (s * 2^l) and u = -H / (s * 2^l), and every b is below n / 2^l. in the lattice
+    // spanned by n * e_i, (t_1 .. t_m, 1 / 2^l, 0) and (u_1 .. u_m, 0, n / 2^l) the vector
+    // (b_1 .. b_m, d / 2^l, -n / 2^l) is unusually short, so LLL should find it. None if it does
+    // not, which usually means too few signatures for the bias
+    let EcGroup { curve, g, n, .. } = &public_key.group;
+    let m = signed.len();
+    let scale = BigUint::one() << zero_bits;
+    let to_rational = |v: &BigUint| BigRational::from_integer(v.clone().into());
+    let mut basis: Vec<Vec<BigRational>> = (0..m)
+        .map(|i| {
+            let mut row = vec![BigRational::zero(); m + 2];
+            row[i] = to_rational(n);
+            row
+        })
+        .collect();
+    let mut t_row = vec![BigRational::zero(); m + 2];
+    let mut u_row = vec![BigRational::zero(); m + 2];
+    for (i, (message, signature)) in signed.iter().enumerate() {
+        let denominator_inv = (&signature.s * &scale % n).modinv(n)?;
+        t_row[i] = to_rational(&(&signature.r * &denominator_inv % n));
+        u_row[i] = to_rational(&mod_sub(
+            &BigUint::zero(),
+            &(ecdsa_hash(message, n) * &denominator_inv % n),
+            n,
+        ));
+    }
+    t_row[m] = BigRational::new(BigInt::one(), BigInt::from(scale.clone()));
+    u_row[m + 1] = BigRational::new(BigInt::from(n.clone()), BigInt::from(scale.clone()));
+    basis.push(t_row);
+    basis.push(u_row);
+    let cu = basis[m + 1][m + 1].clone();
+    lll(&basis)
+        .iter()
+        .filter(|row| row[m + 1].abs() == cu)
+        .find_map(|row| {
+            // the multiple of t_row can be any d + jn, and the row may be negated
+            let x = (&row[m] * BigRational::from_integer(scale.clone().into())).to_integer();
+            let n = BigInt::from(n.clone());
+            [x.mod_floor(&n), (-x).mod_floor(&n)]
+                .into_iter()
+                .filter_map(|d| d.to_biguint())
+                .find(|d| curve.scalar_mul_projective(g, d) == public_key.q)
+        })
+}
@@ -0,0 +1,298 @@
+use crate::bit_matrix::BitMatrix;
+use crate::gf128::{Gf128, Gf128Poly, gf128_mul_matrix, gf128_poly_roots, gf128_square_matrix};
+use crate::{encrypt_aes_128_block, xor_bytes};
+use rand::Rng;
+
+// AES-128-GCM (NIST SP 800-38D) on top of encrypt_aes_128_block, plus the forbidden attack on
+// nonce reuse and Ferguson's attack on truncated tags
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GcmMessage {
+    pub aad: Vec<u8>,
+    pub ciphertext: Vec<u8>,
+    pub tag: Vec<u8>,
+}
+
+pub fn gcm_hash_key(key: &[u8]) -> Gf128 {
+    Gf128::from_block(&encrypt_aes_128_block(&[0u8; 16], key))
+}
+
+fn ghash_blocks(aad: &[u8], ciphertext: &[u8]) -> Vec<Gf128> {
+    // the zero padded aad and ciphertext blocks followed by their lengths in bits
+    let lengths = [
+        (aad.len() as u64 * 8).to_be_bytes(),
+        (ciphertext.len() as u64 * 8).to_be_bytes(),
+    ]
+    .concat();
+    aad.chunks(16)
+        .chain(ciphertext.chunks(16))
+        .chain([lengths.as_slice()])
+        .map(Gf128::from_block)
+        .collect()
+}
+
+pub fn ghash(h: Gf128, aad: &[u8], ciphertext: &[u8]) -> Gf128 {
+    ghash_blocks(aad, ciphertext)
+        .into_iter()
+        .fold(Gf128::ZERO, |y, block| (y + block) * h)
+}
+
+fn gcm_initial_counter(h: Gf128, nonce: &[u8]) -> Vec<u8> {
+    // J0 is nonce || 0^31 || 1 for the usual 96-bit nonce, and the GHASH of the nonce otherwise
+    if nonce.len() == 12 {
+        [nonce, &[0, 0, 0, 1]].concat()
+    } else {
+        ghash(h, &[], nonce).to_block()
+    }
+}
+
+fn inc32(counter: &[u8]) -> Vec<u8> {
+    let low = u32::from_be_bytes(counter[12..].try_into().unwrap()).wrapping_add(1);
+    [&counter[..12], &low.to_be_bytes()].concat()
+}
+
+fn gcm_ctr(input_bytes: &[u8], key: &[u8], initial_counter: &[u8]) -> Vec<u8> {
+    // the keystream starts from inc32(J0); J0 itself masks the tag
+    let mut counter = initial_counter.to_vec();
+    let keystream: Vec<u8> = (0..input_bytes.len().div_ceil(16))
+        .flat_map(|_| {
+            counter = inc32(&counter);
+            encrypt_aes_128_block(&counter, key)
+        })
+        .collect();
+    xor_bytes(input_bytes, &keystream[..input_bytes.len()])
+}
+
+fn gcm_tag(
+    key: &[u8],
+    h: Gf128,
+    initial_counter: &[u8],
+    aad: &[u8],
+    ciphertext: &[u8],
+    tag_len: usize,
+) -> Vec<u8> {
+    // truncated tags are the leftmost tag_len bytes of the full tag
+    assert!((1..=16).contains(&tag_len), "Tags are 1 to 16 bytes.");
+    let mask = Gf128::from_block(&encrypt_aes_128_block(initial_counter, key));
+    let mut tag = (ghash(h, aad, ciphertext) + mask).to_block();
+    tag.truncate(tag_len);
+    tag
+}
+
+pub fn aes_128_gcm_encrypt(
+    plaintext: &[u8],
+    key: &[u8],
+    nonce: &[u8],
+    aad: &[u8],
+) -> (Vec<u8>, Vec<u8>) {
+    aes_128_gcm_encrypt_with_tag_len(plaintext, key, nonce, aad, 16)
+}
+
+pub fn aes_128_gcm_encrypt_with_tag_len(
+    plaintext: &[u8],
+    key: &[u8],
+    nonce: &[u8],
+    aad: &[u8],
+    tag_len: usize,
+) -> (Vec<u8>, Vec<u8>) {
+    // returns (ciphertext, tag)
+    assert!(!nonce.is_empty(), "Nonce must not be empty.");
+    let h = gcm_hash_key(key);
+    let initial_counter = gcm_initial_counter(h, nonce);
+    let ciphertext = gcm_ctr(plaintext, key, &initial_counter);
+    let tag = gcm_tag(key, h, &initial_counter, aad, &ciphertext, tag_len);
+    (ciphertext, tag)
+}
+
+pub fn aes_128_gcm_decrypt(
+    ciphertext: &[u8],
+    key: &[u8],
+    nonce: &[u8],
+    aad: &[u8],
+    tag: &[u8],
+) -> Option<Vec<u8>> {
+    aes_128_gcm_decrypt_with_tag_len(ciphertext, key, nonce, aad, tag, 16)
+}
+
+pub fn aes_128_gcm_decrypt_with_tag_len(
+    ciphertext: &[u8],
+    key: &[u8],
+    nonce: &[u8],
+    aad: &[u8],
+    tag: &[u8],
+    tag_len: usize,
+) -> Option<Vec<u8>> {
+    // None if the tag does not verify
+    assert!(!nonce.is_empty(), "Nonce must not be empty.");
+    let h = gcm_hash_key(key);
+    let initial_counter = gcm_initial_counter(h, nonce);
+    let expected = gcm_tag(key, h, &initial_counter, aad, ciphertext, tag_len);
+    if expected != tag {
+        return None;
+    }
+    Some(gcm_ctr(ciphertext, key, &initial_counter))
+}
+
+pub fn gcm_tag_polynomial(message: &GcmMessage) -> Gf128Poly {
+    // tag = b1 * H^m + ... + bm * H + S, so this polynomial in H evaluates to the tag mask S at
+    // the real authentication key. two messages under one nonce share S, and H is a root of the
+    // difference of their polynomials
+    let blocks = ghash_blocks(&message.aad, &message.ciphertext);
+    let coefficients = [Gf128::from_block(&message.tag)]
+        .into_iter()
+        .chain(blocks.into_iter().rev())
+        .collect();
+    Gf128Poly::new(coefficients)
+}
+
+pub fn gcm_forbidden_attack<R: Rng + ?Sized>(messages: &[GcmMessage], rng: &mut R) -> Vec<Gf128> {
+    // candidates for H from messages that all used the same key and nonce. the first two give a
+    // handful of roots and every further message weeds out the ones it disagrees with
+    assert!(messages.len() >= 2, "Need at least two messages.");
+    let first = gcm_tag_polynomial(&messages[0]);
+    let difference = first.add(&gcm_tag_polynomial(&messages[1]));
+    gf128_poly_roots(&difference, rng)
+        .into_iter()
+        .filter(|&h| {
+            let mask = first.evaluate(h);
+            messages[2..]
+                .iter()
+                .all(|m| gcm_tag_polynomial(m).evaluate(h) == mask)
+        })
+        .collect()
+}
+
+pub fn gcm_forge_tag(h: Gf128, known: &GcmMessage, aad: &[u8], ciphertext: &[u8]) -> Vec<u8> {
+    // with H the tag mask of the reused nonce falls out of any known message, and then any
+    // ciphertext can be given a valid tag
+    let mask = gcm_tag_polynomial(known).evaluate(h);
+    (ghash(h, aad, ciphertext) + mask).to_block()
+}
+
+fn ferguson_kernel(basis: &[Gf128], n: usize, zeroed: usize) -> Vec<Vec<Gf128>> {
+    // the error coefficients d_1 .. d_n for which the first zeroed rows of Ad * X vanish, X
+    // being the candidates for h. row r, column l of Ad * X depends on bit j of d_i through bit r
+    // of x^j * X_l^(2^i), which makes up the dependency matrix
+    let mut dependency = BitMatrix::zero(zeroed * basis.len(), n * 128);
+    for (l, &x) in basis.iter().enumerate() {
+        let mut squared = x;
+        for i in 0..n {
+            squared = squared.square();
+            let mut term = squared;
+            for j in 0..128 {
+                for r in (0..zeroed).filter(|&r| (term.0 >> (127 - r)) & 1 == 1) {
+                    dependency.set(l * zeroed + r, i * 128 + j, true);
+                }
+                term = term.mul_x();
+            }
+        }
+    }
+    dependency
+        .kernel()
+        .iter()
+        .map(|v| v.chunks(128).map(Gf128::from_bits).collect())
+        .collect()
+}
+
+pub fn gcm_ferguson_attack<F, P, R>(
+    ciphertext: &[u8],
+    tag: &[u8],
+    mut is_valid: F,
+    mut progress: P,
+    rng: &mut R,
+) -> Gf128
+where
+    F: FnMut(&[u8], &[u8]) -> bool,
+    P: FnMut(usize),
+    R: Rng + ?Sized,
+{
+    // Ferguson's attack on short tags. xoring d_i into the ciphertext block multiplied by
+    // H^(2^i) changes the tag by sum d_i * H^(2^i), and since squaring is linear that is Ad * h
+    // for the bit matrix Ad = sum M(d_i) * S^i. choosing the d_i in the kernel of the dependency
+    // matrix zeroes the first rows of Ad, so a forgery only has to get the remaining tag bits
+    // right by luck; each one the oracle accepts gives those rows as equations on h. the
+    // candidates for h shrink to the kernel of the equations until only H is left. progress is
+    // called with the dimension of that candidate space.
+    assert_eq!(
+        ciphertext.len() % 16,
+        0,
+        "Ciphertext must be block aligned."
+    );
+    let blocks = ciphertext.len() / 16;
+    let n = (blocks + 1).ilog2() as usize;
+    assert!(n >= 2, "Ciphertext is too short.");
+    let tag_bits = tag.len() * 8;
+    let square = gf128_square_matrix();
+    let squarings: Vec<BitMatrix> = (0..n)
+        .scan(BitMatrix::identity(128), |s, _| {
+            *s = square.mul(s);
+            Some(s.clone())
+        })
+        .collect();
+    // one copy of the ciphertext, with each forgery xored in and out again, so a query costs
+    // the same however long the ciphertext is
+    let mut forged = ciphertext.to_vec();
+    let toggle = |forged: &mut [u8], d: &[Gf128]| {
+        for (i, di) in d.iter().enumerate() {
+            // the block multiplied by H^(2^(i + 1)), counting the length block as H^1
+            let start = (blocks + 1 - (2 << i)) * 16;
+            for (byte, x) in forged[start..start + 16].iter_mut().zip(di.to_block()) {
+                *byte ^= x;
+            }
+        }
+    };
+    let mut equations = BitMatrix::zero(0, 128);
+    let mut basis: Vec<Gf128> = (0..128).map(|i| Gf128(1 << (127 - i))).collect();
+    let mut zeroed = 0;
+    let mut kernel: Vec<Vec<Gf128>> = vec![];
+    while basis.len() > 1 {
+        progress(basis.len());
+        // a kernel that zeroes rows for every candidate keeps doing so as the candidates shrink,
+        // so it only needs rebuilding when more rows can be zeroed or it has run out of new
+        // equations to give
+        let can_zero = (tag_bits - 1).min((n * 128 - 1) / basis.len());
+        if can_zero > zeroed {
+            zeroed = can_zero;
+            kernel = ferguson_kernel(&basis, n, zeroed);
+        }
+        // forge until the oracle accepts something that tells us more about h
+        let dimension = basis.len();
+        while basis.len() == dimension {
+            let d = kernel
+                .iter()
+                .filter(|_| rng.random::<bool>())
+                .fold(vec![Gf128::ZERO; n], |d, v| {
+                    d.iter().zip(v).map(|(&a, &b)| a + b).collect()
+                });
+            if d.iter().all(|di| di.is_zero()) {
+                continue;
+            }
+            // xoring d in a second time puts the ciphertext back for the next try
+            toggle(&mut forged, &d);
+            let valid = is_valid(&forged, tag);
+            toggle(&mut forged, &d);
+            if !valid {
+                continue;
+            }
+            // only the rows of Ad past the zeroed ones say anything
+            let ad_rows = d
+                .iter()
+                .zip(&squarings)
+                .map(|(&di, s)| gf128_mul_matrix(di).select_rows(zeroed..tag_bits).mul(s))
+                .reduce(|a, b| a.add(&b))
+                .unwrap();
+            equations = equations.stack(&ad_rows);
+            basis = equations
+                .kernel()
+                .iter()
+                .map(|v| Gf128::from_bits(v))
+                .collect();
+            if basis.len() == dimension {
+                kernel = ferguson_kernel(&basis, n, zeroed);
+            }
+        }
+    }
+    assert_eq!(basis.len(), 1, "Equations ruled out every key.");
+    progress(1);
+    basis[0]
+}
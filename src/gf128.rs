@@ -0,0 +1,317 @@
+use crate::bit_matrix::BitMatrix;
+use rand::Rng;
+use std::ops::{Add, Mul};
+
+// an element of GF(2^128) in GCM's bit order: a block read as a big endian u128, where the most
+// significant bit is the coefficient of x^0 and the field is reduced by x^128 + x^7 + x^2 + x + 1
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Gf128(pub u128);
+
+const GF128_R: u128 = 0xe1 << 120;
+
+impl Gf128 {
+    pub const ZERO: Gf128 = Gf128(0);
+    pub const ONE: Gf128 = Gf128(1 << 127);
+
+    pub fn from_block(block: &[u8]) -> Self {
+        // blocks shorter than 16 bytes are zero padded on the right
+        assert!(block.len() <= 16, "Block must be at most 16 bytes.");
+        let mut buf = [0u8; 16];
+        buf[..block.len()].copy_from_slice(block);
+        Gf128(u128::from_be_bytes(buf))
+    }
+
+    pub fn to_block(self) -> Vec<u8> {
+        self.0.to_be_bytes().to_vec()
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn square(self) -> Self {
+        self * self
+    }
+
+    pub fn pow(self, mut exponent: u128) -> Self {
+        let mut base = self;
+        let mut result = Gf128::ONE;
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result * base;
+            }
+            base = base.square();
+            exponent >>= 1;
+        }
+        result
+    }
+
+    pub fn inverse(self) -> Self {
+        // a^(2^128 - 2) = a^-1 since the multiplicative group has order 2^128 - 1
+        assert!(!self.is_zero(), "Zero has no inverse.");
+        self.pow(u128::MAX - 1)
+    }
+
+    pub fn sqrt(self) -> Self {
+        // squaring is a bijection here, and squaring 128 times is the identity
+        (0..127).fold(self, |a, _| a.square())
+    }
+
+    pub fn mul_x(self) -> Self {
+        // multiplying by x is a shift towards the low end of the u128, reducing on overflow
+        Gf128(if self.0 & 1 == 1 {
+            (self.0 >> 1) ^ GF128_R
+        } else {
+            self.0 >> 1
+        })
+    }
+
+    pub fn to_bits(self) -> Vec<bool> {
+        // bit i is the coefficient of x^i, so the first bits are the first bits of the block
+        (0..128).map(|i| (self.0 >> (127 - i)) & 1 == 1).collect()
+    }
+
+    pub fn from_bits(bits: &[bool]) -> Self {
+        assert_eq!(bits.len(), 128, "Need 128 bits.");
+        Gf128(bits.iter().fold(0, |acc, &b| (acc << 1) | b as u128))
+    }
+}
+
+// addition in characteristic two is xor
+#[allow(clippy::suspicious_arithmetic_impl)]
+impl Add for Gf128 {
+    type Output = Gf128;
+
+    fn add(self, other: Gf128) -> Gf128 {
+        Gf128(self.0 ^ other.0)
+    }
+}
+
+impl Mul for Gf128 {
+    type Output = Gf128;
+
+    fn mul(self, other: Gf128) -> Gf128 {
+        // the shift and add multiply from the GCM spec
+        let mut z = 0u128;
+        let mut v = other.0;
+        for i in (0..128).rev() {
+            if (self.0 >> i) & 1 == 1 {
+                z ^= v;
+            }
+            v = Gf128(v).mul_x().0;
+        }
+        Gf128(z)
+    }
+}
+
+// multiplying by a constant and squaring are both GF(2)-linear maps on the 128 bits of an element
+pub fn gf128_mul_matrix(c: Gf128) -> BitMatrix {
+    // column i is c * x^i
+    let columns: Vec<Vec<bool>> = (0..128)
+        .map(|i| (c * Gf128(1 << (127 - i))).to_bits())
+        .collect();
+    BitMatrix::from_columns(&columns)
+}
+
+pub fn gf128_square_matrix() -> BitMatrix {
+    // column i is (x^i)^2
+    let columns: Vec<Vec<bool>> = (0..128)
+        .map(|i| Gf128(1 << (127 - i)).square().to_bits())
+        .collect();
+    BitMatrix::from_columns(&columns)
+}
+
+// a polynomial over GF(2^128), coefficients lowest degree first and never with trailing zeros
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Gf128Poly(pub Vec<Gf128>);
+
+impl Gf128Poly {
+    pub fn new(mut coefficients: Vec<Gf128>) -> Self {
+        while coefficients.last().is_some_and(|c| c.is_zero()) {
+            coefficients.pop();
+        }
+        Gf128Poly(coefficients)
+    }
+
+    pub fn zero() -> Self {
+        Gf128Poly(vec![])
+    }
+
+    pub fn one() -> Self {
+        Gf128Poly(vec![Gf128::ONE])
+    }
+
+    pub fn x() -> Self {
+        Gf128Poly(vec![Gf128::ZERO, Gf128::ONE])
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn degree(&self) -> Option<usize> {
+        self.0.len().checked_sub(1)
+    }
+
+    pub fn leading(&self) -> Gf128 {
+        self.0.last().copied().unwrap_or(Gf128::ZERO)
+    }
+
+    pub fn evaluate(&self, x: Gf128) -> Gf128 {
+        self.0.iter().rev().fold(Gf128::ZERO, |acc, &c| acc * x + c)
+    }
+
+    pub fn add(&self, other: &Gf128Poly) -> Gf128Poly {
+        let len = self.0.len().max(other.0.len());
+        let get = |p: &Gf128Poly, i: usize| p.0.get(i).copied().unwrap_or(Gf128::ZERO);
+        Gf128Poly::new((0..len).map(|i| get(self, i) + get(other, i)).collect())
+    }
+
+    pub fn mul(&self, other: &Gf128Poly) -> Gf128Poly {
+        if self.is_zero() || other.is_zero() {
+            return Gf128Poly::zero();
+        }
+        let mut product = vec![Gf128::ZERO; self.0.len() + other.0.len() - 1];
+        for (i, &a) in self.0.iter().enumerate() {
+            for (j, &b) in other.0.iter().enumerate() {
+                product[i + j] = product[i + j] + a * b;
+            }
+        }
+        Gf128Poly::new(product)
+    }
+
+    pub fn scale(&self, c: Gf128) -> Gf128Poly {
+        Gf128Poly::new(self.0.iter().map(|&a| a * c).collect())
+    }
+
+    pub fn monic(&self) -> Gf128Poly {
+        if self.is_zero() {
+            return self.clone();
+        }
+        self.scale(self.leading().inverse())
+    }
+
+    pub fn divrem(&self, divisor: &Gf128Poly) -> (Gf128Poly, Gf128Poly) {
+        // returns (quotient, remainder)
+        let divisor_degree = divisor.degree().expect("Division by zero polynomial.");
+        let lead_inverse = divisor.leading().inverse();
+        let mut remainder = self.0.clone();
+        let mut quotient = vec![Gf128::ZERO; self.0.len().saturating_sub(divisor_degree)];
+        while remainder.len() > divisor_degree {
+            let shift = remainder.len() - 1 - divisor_degree;
+            let c = *remainder.last().unwrap() * lead_inverse;
+            quotient[shift] = c;
+            for (i, &d) in divisor.0.iter().enumerate() {
+                remainder[shift + i] = remainder[shift + i] + c * d;
+            }
+            remainder.pop();
+            while remainder.last().is_some_and(|c| c.is_zero()) {
+                remainder.pop();
+            }
+        }
+        (Gf128Poly::new(quotient), Gf128Poly::new(remainder))
+    }
+
+    pub fn rem(&self, divisor: &Gf128Poly) -> Gf128Poly {
+        self.divrem(divisor).1
+    }
+
+    pub fn mul_mod(&self, other: &Gf128Poly, modulus: &Gf128Poly) -> Gf128Poly {
+        self.mul(other).rem(modulus)
+    }
+
+    pub fn gcd(&self, other: &Gf128Poly) -> Gf128Poly {
+        // monic, or zero if both are zero
+        let (mut a, mut b) = (self.clone(), other.clone());
+        while !b.is_zero() {
+            let r = a.rem(&b);
+            a = b;
+            b = r;
+        }
+        a.monic()
+    }
+
+    pub fn frobenius_mod(&self, times: usize, modulus: &Gf128Poly) -> Gf128Poly {
+        // self^(q^times) mod modulus for q = 2^128, by squaring 128 * times times
+        (0..128 * times).fold(self.rem(modulus), |p, _| p.mul_mod(&p, modulus))
+    }
+}
+
+pub fn random_gf128_poly<R: Rng + ?Sized>(degree_below: usize, rng: &mut R) -> Gf128Poly {
+    Gf128Poly::new((0..degree_below).map(|_| Gf128(rng.random())).collect())
+}
+
+pub fn distinct_degree_factorization(f: &Gf128Poly) -> Vec<(Gf128Poly, usize)> {
+    // f must be monic and square free. returns (g, d) where g is the product of all irreducible
+    // factors of f of degree d
+    let mut factors = vec![];
+    let mut f = f.clone();
+    let mut h = Gf128Poly::x();
+    let mut d = 1;
+    while f.degree().unwrap_or(0) >= 2 * d {
+        h = h.frobenius_mod(1, &f);
+        let g = f.gcd(&h.add(&Gf128Poly::x()));
+        if g != Gf128Poly::one() {
+            f = f.divrem(&g).0;
+            h = h.rem(&f);
+            factors.push((g, d));
+        }
+        d += 1;
+    }
+    if f.degree().unwrap_or(0) > 0 {
+        let d = f.degree().unwrap();
+        factors.push((f, d));
+    }
+    factors
+}
+
+pub fn equal_degree_factorization<R: Rng + ?Sized>(
+    f: &Gf128Poly,
+    d: usize,
+    rng: &mut R,
+) -> Vec<Gf128Poly> {
+    // Cantor-Zassenhaus splitting of a monic, square free f whose irreducible factors all have
+    // degree d. in characteristic two the trace map a + a^2 + a^4 + ... + a^(2^(128d - 1)) takes
+    // the place of a^((q^d - 1) / 2), and is zero modulo about half of the factors
+    let n = f.degree().expect("Cannot factor the zero polynomial.");
+    if n <= d {
+        return vec![f.clone()];
+    }
+    loop {
+        let a = random_gf128_poly(n, rng);
+        let mut term = a.clone();
+        let mut trace = a;
+        for _ in 1..128 * d {
+            term = term.mul_mod(&term, f);
+            trace = trace.add(&term);
+        }
+        let g = f.gcd(&trace);
+        if g.degree().is_some_and(|k| k > 0 && k < n) {
+            let h = f.divrem(&g).0.monic();
+            return [
+                equal_degree_factorization(&g, d, rng),
+                equal_degree_factorization(&h, d, rng),
+            ]
+            .concat();
+        }
+    }
+}
+
+pub fn gf128_poly_roots<R: Rng + ?Sized>(f: &Gf128Poly, rng: &mut R) -> Vec<Gf128> {
+    // gcd(f, x^q - x) is the product of the distinct linear factors of f, even when f is not
+    // square free, and those split with equal_degree_factorization
+    let f = f.monic();
+    if f.degree().unwrap_or(0) == 0 {
+        return vec![];
+    }
+    let linear = f.gcd(&Gf128Poly::x().frobenius_mod(1, &f).add(&Gf128Poly::x()));
+    if linear.degree().unwrap_or(0) == 0 {
+        return vec![];
+    }
+    let mut roots: Vec<Gf128> = equal_degree_factorization(&linear, 1, rng)
+        .iter()
+        .map(|factor| factor.0[0])
+        .collect();
+    roots.sort_by_key(|r| r.0);
+    roots
+}
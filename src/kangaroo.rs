@@ -0,0 +1,87 @@
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+
+// Pollard's kangaroo (lambda) method for discrete logarithms known to lie in an interval, over
+// any cyclic group that can multiply, exponentiate and hash its elements to a jump
+
+pub trait KangarooGroup {
+    type Element: Clone + PartialEq;
+
+    fn combine(&self, a: &Self::Element, b: &Self::Element) -> Self::Element;
+
+    fn power(&self, a: &Self::Element, k: &BigUint) -> Self::Element;
+
+    // a pseudorandom index in [0, jumps) that depends only on the element
+    fn jump_index(&self, a: &Self::Element, jumps: u32) -> usize;
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KangarooParams {
+    // the kangaroos jump 2^i steps for i in [0, jumps)
+    pub jumps: u32,
+    // the tame kangaroo makes trap_factor times the mean jump size jumps before settling
+    pub trap_factor: u32,
+}
+
+impl KangarooParams {
+    pub fn for_width(width: &BigUint) -> Self {
+        // the mean jump comes out at about sqrt(width) / 2, which makes the whole search about
+        // 2 sqrt(width) group operations
+        let sqrt_width = width.sqrt();
+        let jumps = (1u32..)
+            .find(|&k| (BigUint::one() << k) > &sqrt_width * k / 2u32)
+            .unwrap();
+        KangarooParams {
+            jumps,
+            trap_factor: 4,
+        }
+    }
+}
+
+pub fn kangaroo<G: KangarooGroup>(
+    group: &G,
+    g: &G::Element,
+    y: &G::Element,
+    lower: &BigUint,
+    upper: &BigUint,
+    params: &KangarooParams,
+) -> Option<BigUint> {
+    // some k with y = g^k, found when k is in [lower, upper). None means the wild kangaroo ran
+    // past the trap, either because k is outside the interval or through bad luck, which a
+    // larger trap_factor makes rarer
+    assert!(lower <= upper, "Interval is empty.");
+    assert!(params.jumps > 0, "Need at least one jump size.");
+    let width = upper - lower;
+    let jumps: Vec<(BigUint, G::Element)> = (0..params.jumps)
+        .map(|i| {
+            let size = BigUint::one() << i;
+            let element = group.power(g, &size);
+            (size, element)
+        })
+        .collect();
+    let jump = |a: &G::Element| &jumps[group.jump_index(a, params.jumps)];
+    // the tame kangaroo sets a trap some way past the top of the interval
+    let mean: BigUint = jumps.iter().map(|(size, _)| size).sum::<BigUint>() / params.jumps;
+    let steps = mean * params.trap_factor;
+    let mut tame_distance = BigUint::zero();
+    let mut tame = group.power(g, upper);
+    let mut n = BigUint::zero();
+    while n < steps {
+        let (size, element) = jump(&tame);
+        tame_distance += size;
+        tame = group.combine(&tame, element);
+        n += 1u32;
+    }
+    // the wild kangaroo either lands in the trap or runs past it
+    let mut wild_distance = BigUint::zero();
+    let mut wild = y.clone();
+    while wild_distance <= &width + &tame_distance {
+        if wild == tame {
+            return Some(upper + tame_distance - wild_distance);
+        }
+        let (size, element) = jump(&wild);
+        wild_distance += size;
+        wild = group.combine(&wild, element);
+    }
+    None
+}
@@ -3,10 +3,34 @@ use aes::cipher::{BlockDecrypt, BlockEncrypt, KeyInit, generic_array::GenericArr
 use base64::prelude::*;
 use encoding_rs::mem::convert_utf8_to_latin1_lossy;
 use hex::FromHex;
+use hmac::{Hmac, Mac};
 use rand::Rng;
+use scoring::{LetterTableScorer, Scorer};
+use sha2::{Digest, Sha256};
 use std::fs::File;
 use std::io::{BufRead, BufReader};
-use std::ops::Range;
+
+pub mod bignum;
+pub mod bit_matrix;
+pub mod cbc_mac;
+pub mod collision;
+pub mod compression_oracle;
+pub mod dh;
+pub mod dsa;
+pub mod ec;
+pub mod gcm;
+pub mod gf128;
+pub mod kangaroo;
+pub mod lll;
+pub mod many_time_pad;
+pub mod md_hash;
+pub mod rsa;
+pub mod scoring;
+pub mod xor;
+pub mod xor_variants;
+
+// (key, plaintext, score)
+type KeyPlaintextScore = (Vec<u8>, Vec<u8>, f64);
 
 pub fn hex_file_to_bytes(filepath: &str) -> Vec<Vec<u8>> {
     let file = File::open(filepath).expect("Unable to open file.");
@@ -38,7 +62,7 @@ pub fn b64_to_bytes(s: &str) -> Vec<u8> {
     BASE64_STANDARD.decode(s).unwrap()
 }
 
-pub fn xor_bytes(input: &Vec<u8>, key: &Vec<u8>) -> Vec<u8> {
+pub fn xor_bytes(input: &[u8], key: &[u8]) -> Vec<u8> {
     input
         .iter()
         .enumerate()
@@ -53,17 +77,17 @@ pub fn string_to_bytes(s: &str) -> Vec<u8> {
     convert_utf8_to_latin1_lossy(utf8_bytes, &mut u8_bytes);
     let mut u8_vec = vec![];
     for b in u8_bytes.iter() {
-        u8_vec.push(b.clone())
+        u8_vec.push(*b)
     }
     u8_vec.truncate(len_in_chars);
     u8_vec
 }
 
-pub fn brute_single_byte_xor_cipher(input_bytes: &Vec<u8>) -> Vec<(Vec<u8>, Vec<u8>)> {
-    let keys: Vec<Vec<u8>> = Range { start: 0, end: 254 }.map(|b: u8| vec![b]).collect();
+pub fn brute_single_byte_xor_cipher(input_bytes: &[u8]) -> Vec<(Vec<u8>, Vec<u8>)> {
+    let keys: Vec<Vec<u8>> = (0..=255).map(|b: u8| vec![b]).collect();
     keys.iter()
         .map(|k| {
-            let plaintext = xor_bytes(&input_bytes, k);
+            let plaintext = xor_bytes(input_bytes, k);
             (k.clone(), plaintext)
         })
         .collect()
@@ -91,61 +115,64 @@ pub fn score_byte(b: &u8) -> f32 {
     }
 }
 
-pub fn score_bytes(bytes: &Vec<u8>) -> i32 {
-    let score = bytes.iter().map(|b| score_byte(b)).sum::<f32>().round();
+pub fn score_bytes(bytes: &[u8]) -> i32 {
+    let score = bytes.iter().map(score_byte).sum::<f32>().round();
     score as i32
 }
 
 pub fn highest_scoring_plaintext(
-    keys_plaintexts: &Vec<(Vec<u8>, Vec<u8>)>,
+    keys_plaintexts: &[(Vec<u8>, Vec<u8>)],
 ) -> (Vec<u8>, Vec<u8>, i32) {
     // returns (key, plaintext, score)
+    let (key, plaintext, score) =
+        highest_scoring_plaintext_with(keys_plaintexts, &LetterTableScorer);
+    (key, plaintext, score as i32)
+}
+
+pub fn highest_scoring_plaintext_with<S: Scorer + ?Sized>(
+    keys_plaintexts: &[(Vec<u8>, Vec<u8>)],
+    scorer: &S,
+) -> KeyPlaintextScore {
     keys_plaintexts
         .iter()
-        .map(|kp| (kp.0.clone(), kp.1.clone(), score_bytes(&kp.1)))
-        .max_by(|kps_x, kps_y| kps_x.2.cmp(&kps_y.2))
+        .map(|kp| (kp.0.clone(), kp.1.clone(), scorer.score(&kp.1)))
+        .max_by(|kps_x, kps_y| kps_x.2.total_cmp(&kps_y.2))
         .unwrap()
-        .clone()
 }
 
 pub fn break_single_character_xor(input_bytes: Vec<Vec<u8>>) -> (Vec<u8>, Vec<u8>, i32) {
+    let (key, plaintext, score) = break_single_character_xor_with(input_bytes, &LetterTableScorer);
+    (key, plaintext, score as i32)
+}
+
+pub fn break_single_character_xor_with<S: Scorer + ?Sized>(
+    input_bytes: Vec<Vec<u8>>,
+    scorer: &S,
+) -> KeyPlaintextScore {
     input_bytes
         .iter()
-        .map(|v| highest_scoring_plaintext(&brute_single_byte_xor_cipher(v)))
-        .max_by(|kps_x, kps_y| kps_x.2.cmp(&kps_y.2))
+        .map(|v| highest_scoring_plaintext_with(&brute_single_byte_xor_cipher(v), scorer))
+        .max_by(|kps_x, kps_y| kps_x.2.total_cmp(&kps_y.2))
         .unwrap()
-        .clone()
-}
-
-pub fn guess_xor_keysize(input_bytes: &Vec<u8>) -> Vec<u32> {
-    let mut sizes_distances: Vec<(u32, u32)> = vec![];
-    let sizes: Vec<usize> = (2usize..=60).collect();
-    for keysize in sizes {
-        let keysize_u32 = keysize as u32;
-        let chunks: Vec<_> = input_bytes.chunks(keysize).take(8).collect();
-        let avg = (0..3)
-            .map(|i| hamming::distance(chunks[i], chunks[i + 1]) as u32 / keysize_u32)
-            .sum::<u32>()
-            / 4;
-        sizes_distances.push((keysize_u32, avg));
-    }
-    sizes_distances.sort_by(|kd1, kd2| kd1.1.cmp(&kd2.1));
-    sizes_distances
+}
+
+pub fn guess_xor_keysize(input_bytes: &[u8]) -> Vec<u32> {
+    // the ten likeliest keysizes by normalised Hamming distance, best first
+    xor::estimate_keysizes(input_bytes, xor::KeysizeMethod::Hamming)
         .into_iter()
         .take(10)
-        .clone()
-        .map(|tup| tup.0)
+        .map(|(keysize, _)| keysize as u32)
         .collect()
 }
 
-pub fn partition(input_bytes: &Vec<u8>, size: &u32) -> Vec<Vec<u8>> {
+pub fn partition(input_bytes: &[u8], size: &u32) -> Vec<Vec<u8>> {
     input_bytes
         .chunks(*size as usize)
         .map(|chunk| chunk.to_vec())
         .collect()
 }
 
-pub fn transpose(bytes: &Vec<Vec<u8>>) -> Vec<Vec<u8>> {
+pub fn transpose(bytes: &[Vec<u8>]) -> Vec<Vec<u8>> {
     // Assume that all inner vecs will be of the same length
     // except for (possibly) the last
     let vec_len = bytes[0].len();
@@ -164,18 +191,27 @@ pub fn transpose(bytes: &Vec<Vec<u8>>) -> Vec<Vec<u8>> {
     transposed
 }
 
-pub fn break_repeating_key_xor(input_bytes: &Vec<u8>, keysizes: Vec<u32>) -> (Vec<u8>, Vec<u8>) {
-    let mut keysize_results: Vec<Vec<(Vec<u8>, Vec<u8>, i32)>> = vec![];
+pub fn break_repeating_key_xor(input_bytes: &[u8], keysizes: Vec<u32>) -> (Vec<u8>, Vec<u8>) {
+    break_repeating_key_xor_with(input_bytes, keysizes, &LetterTableScorer)
+}
+
+pub fn break_repeating_key_xor_with<S: Scorer + ?Sized>(
+    input_bytes: &[u8],
+    keysizes: Vec<u32>,
+    scorer: &S,
+) -> (Vec<u8>, Vec<u8>) {
+    // each column is scored on its own, so n-gram scorers only see every keysize-th byte
+    let mut keysize_results: Vec<Vec<KeyPlaintextScore>> = vec![];
     for keysize in keysizes {
-        let partitioned = partition(&input_bytes, &keysize);
+        let partitioned = partition(input_bytes, &keysize);
         let transposed = transpose(&partitioned);
         let keys_plaintexts: Vec<Vec<(Vec<u8>, Vec<u8>)>> = transposed
             .iter()
             .map(|v| brute_single_byte_xor_cipher(v))
             .collect();
-        let keys_plaintexts_scores: Vec<(Vec<u8>, Vec<u8>, i32)> = keys_plaintexts
+        let keys_plaintexts_scores: Vec<KeyPlaintextScore> = keys_plaintexts
             .iter()
-            .map(|v| highest_scoring_plaintext(&v))
+            .map(|v| highest_scoring_plaintext_with(v, scorer))
             .collect();
         keysize_results.push(keys_plaintexts_scores);
     }
@@ -184,59 +220,56 @@ pub fn break_repeating_key_xor(input_bytes: &Vec<u8>, keysizes: Vec<u32>) -> (Ve
         .iter()
         .map(|v| {
             v.iter()
-                .map(|(single_byte_key, _, _)| single_byte_key.clone())
-                .flatten()
+                .flat_map(|(single_byte_key, _, _)| single_byte_key.clone())
                 .collect()
         })
         .collect();
-    let avg_scores: Vec<i32> = keysize_results
+    let avg_scores: Vec<f64> = keysize_results
         .iter()
-        .map(|v| v.iter().map(|(_, _, score)| score).sum::<i32>() / v.len() as i32)
+        .map(|v| v.iter().map(|(_, _, score)| score).sum::<f64>() / v.len() as f64)
         .collect();
     let key: Vec<u8> = itertools::izip!(repeating_keys, avg_scores)
-        .max_by(|(_, score_a), (_, score_b)| score_a.cmp(&score_b))
+        .max_by(|(_, score_a), (_, score_b)| score_a.total_cmp(score_b))
         .unwrap()
         .0;
-    (key.to_owned(), xor_bytes(&input_bytes, &key))
+    (key.to_owned(), xor_bytes(input_bytes, &key))
 }
 
-pub fn decrypt_aes_128_block(input_bytes: &Vec<u8>, key: &Vec<u8>) -> Vec<u8> {
-    let mut input_bytes_mut = *GenericArray::from_slice(&input_bytes);
-    let key_arr = GenericArray::from_slice(&key);
-    let cipher = Aes128::new(&key_arr);
+pub fn decrypt_aes_128_block(input_bytes: &[u8], key: &[u8]) -> Vec<u8> {
+    let mut input_bytes_mut = *GenericArray::from_slice(input_bytes);
+    let key_arr = GenericArray::from_slice(key);
+    let cipher = Aes128::new(key_arr);
     cipher.decrypt_block(&mut input_bytes_mut);
     input_bytes_mut.to_vec()
 }
 
-pub fn encrypt_aes_128_block(input_bytes: &Vec<u8>, key: &Vec<u8>) -> Vec<u8> {
-    let mut input_bytes_mut = *GenericArray::from_slice(&input_bytes);
-    let key_arr = GenericArray::from_slice(&key);
-    let cipher = Aes128::new(&key_arr);
+pub fn encrypt_aes_128_block(input_bytes: &[u8], key: &[u8]) -> Vec<u8> {
+    let mut input_bytes_mut = *GenericArray::from_slice(input_bytes);
+    let key_arr = GenericArray::from_slice(key);
+    let cipher = Aes128::new(key_arr);
     cipher.encrypt_block(&mut input_bytes_mut);
     input_bytes_mut.to_vec()
 }
 
-pub fn decrypt_aes_128_ecb(input_bytes: &Vec<u8>, key: &Vec<u8>) -> Vec<u8> {
+pub fn decrypt_aes_128_ecb(input_bytes: &[u8], key: &[u8]) -> Vec<u8> {
     let partitioned: Vec<Vec<u8>> = partition(input_bytes, &16u32);
     partitioned
         .iter()
-        .map(|v| decrypt_aes_128_block(v, key))
-        .flatten()
+        .flat_map(|v| decrypt_aes_128_block(v, key))
         .collect()
 }
 
-pub fn encrypt_aes_128_ecb(input_bytes: &Vec<u8>, key: &Vec<u8>) -> Vec<u8> {
+pub fn encrypt_aes_128_ecb(input_bytes: &[u8], key: &[u8]) -> Vec<u8> {
     let partitioned: Vec<Vec<u8>> = partition(input_bytes, &16u32);
     partitioned
         .iter()
-        .map(|v| encrypt_aes_128_block(v, key))
-        .flatten()
+        .flat_map(|v| encrypt_aes_128_block(v, key))
         .collect()
 }
 
 pub fn detect_ecb(enc_bytes: Vec<Vec<u8>>) -> Vec<Vec<u8>> {
     let mut partitioned: Vec<Vec<Vec<u8>>> =
-        enc_bytes.iter().map(|v| partition(&v, &16u32)).collect();
+        enc_bytes.iter().map(|v| partition(v, &16u32)).collect();
     let mut ecb_blocks: Vec<Vec<u8>> = vec![];
     for v in partitioned.iter_mut() {
         let initial_v = v.clone();
@@ -273,10 +306,10 @@ pub fn pkcs7_pad(input_bytes: Vec<u8>, desired_block_size: u8) -> Vec<u8> {
     }
 }
 
-pub fn encrypt_aes_128_cbc(input_bytes: &Vec<u8>, key: &Vec<u8>, iv: &Vec<u8>) -> Vec<u8> {
+pub fn encrypt_aes_128_cbc(input_bytes: &[u8], key: &[u8], iv: &[u8]) -> Vec<u8> {
     // for each block, xor the plaintext against the previous ciphertext (or IV) block, then encrypt
-    let mut iv_mut: Vec<u8> = iv.clone();
-    let mut input_bytes_mut: Vec<u8> = input_bytes.clone();
+    let mut iv_mut: Vec<u8> = iv.to_vec();
+    let mut input_bytes_mut: Vec<u8> = input_bytes.to_vec();
     iv_mut.append(&mut input_bytes_mut);
     let partitioned = partition(&iv_mut, &16u32);
     let mut cbc_encrypted: Vec<Vec<u8>> = vec![];
@@ -288,28 +321,54 @@ pub fn encrypt_aes_128_cbc(input_bytes: &Vec<u8>, key: &Vec<u8>, iv: &Vec<u8>) -
                 cbc_encrypted.get(i - 2).unwrap()
             }
         };
-        let xord = xor_bytes(&prior_ciphertext_block, partitioned.get(i).unwrap());
-        let aes_encrypted = encrypt_aes_128_block(&xord, &key);
+        let xord = xor_bytes(prior_ciphertext_block, partitioned.get(i).unwrap());
+        let aes_encrypted = encrypt_aes_128_block(&xord, key);
         cbc_encrypted.push(aes_encrypted);
     }
     cbc_encrypted.into_iter().flatten().collect()
 }
 
-pub fn decrypt_aes_128_cbc(input_bytes: &Vec<u8>, key: &Vec<u8>, iv: &Vec<u8>) -> Vec<u8> {
+pub fn decrypt_aes_128_cbc(input_bytes: &[u8], key: &[u8], iv: &[u8]) -> Vec<u8> {
     // for each block, decrypt then xor that plaintext against the previous ciphertext block
-    let mut iv_mut: Vec<u8> = iv.clone();
-    let mut input_bytes_mut: Vec<u8> = input_bytes.clone();
+    let mut iv_mut: Vec<u8> = iv.to_vec();
+    let mut input_bytes_mut: Vec<u8> = input_bytes.to_vec();
     iv_mut.append(&mut input_bytes_mut);
     let partitioned = partition(&iv_mut, &16u32);
     let mut cbc_decrypted: Vec<Vec<u8>> = vec![];
     for i in 1..partitioned.len() {
-        let aes_decrypted = decrypt_aes_128_block(&partitioned[i], &key);
+        let aes_decrypted = decrypt_aes_128_block(&partitioned[i], key);
         let xord = xor_bytes(&aes_decrypted, partitioned.get(i - 1).unwrap());
         cbc_decrypted.push(xord);
     }
     cbc_decrypted.into_iter().flatten().collect()
 }
 
+pub fn aes_128_ctr(input_bytes: &[u8], key: &[u8], nonce: u64) -> Vec<u8> {
+    // keystream blocks are E(nonce || counter), both 64-bit little endian; encrypting and
+    // decrypting are the same operation
+    let keystream: Vec<u8> = (0..input_bytes.len().div_ceil(16) as u64)
+        .flat_map(|counter| {
+            let block = [nonce.to_le_bytes(), counter.to_le_bytes()].concat();
+            encrypt_aes_128_block(&block, key)
+        })
+        .collect();
+    xor_bytes(input_bytes, &keystream)
+}
+
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut mac =
+        <Hmac<Sha256> as Mac>::new_from_slice(key).expect("HMAC takes keys of any length.");
+    mac.update(message);
+    mac.finalize().into_bytes().to_vec()
+}
+
+// the message Bob MACs with a DH or ECDH shared secret in challenges 57 to 60
+pub const SHARED_SECRET_MAC_MESSAGE: &[u8] = b"crazy flamboyant for the rap enjoyment";
+
+pub fn shared_secret_mac(shared_secret: &[u8]) -> Vec<u8> {
+    hmac_sha256(&Sha256::digest(shared_secret), SHARED_SECRET_MAC_MESSAGE)
+}
+
 pub fn rand_bytes() -> Vec<u8> {
     let mut buf = [0u8; 16];
     getrandom::fill(&mut buf).expect("Unable to obtain bytes.");
@@ -0,0 +1,254 @@
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_rational::BigRational;
+use num_traits::{One, Signed, Zero};
+
+// Lenstra-Lenstra-Lovasz lattice basis reduction in exact rational arithmetic. a basis is a list
+// of linearly independent row vectors of the same length
+
+pub fn dot(a: &[BigRational], b: &[BigRational]) -> BigRational {
+    assert_eq!(a.len(), b.len(), "Vectors must be the same length.");
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+pub fn integer_vector(v: &[BigInt]) -> Vec<BigRational> {
+    v.iter().cloned().map(BigRational::from_integer).collect()
+}
+
+fn int_dot(a: &[BigInt], b: &[BigInt]) -> BigInt {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+// the Gram-Schmidt data of an integer basis kept in integers, as in Cohen's integral LLL:
+// d[i] = |b*_0|^2 ... |b*_(i - 1)|^2 with d[0] = 1, and lambda[i][j] = d[j + 1] * mu[i][j]. both
+// are exact integers, so no rational ever needs normalising
+struct IntegralGramSchmidt {
+    d: Vec<BigInt>,
+    lambda: Vec<Vec<BigInt>>,
+}
+
+impl IntegralGramSchmidt {
+    fn new(basis: &[Vec<BigInt>]) -> Self {
+        let n = basis.len();
+        let mut d = vec![BigInt::one(); n + 1];
+        let mut lambda = vec![vec![BigInt::zero(); n]; n];
+        for k in 0..n {
+            for j in 0..=k {
+                let mut u = int_dot(&basis[k], &basis[j]);
+                for i in 0..j {
+                    u = (&d[i + 1] * u - &lambda[k][i] * &lambda[j][i]) / &d[i];
+                }
+                if j < k {
+                    lambda[k][j] = u;
+                } else {
+                    assert!(!u.is_zero(), "Basis vectors must be linearly independent.");
+                    d[k + 1] = u;
+                }
+            }
+        }
+        IntegralGramSchmidt { d, lambda }
+    }
+
+    fn size_reduce(&mut self, b: &mut [Vec<BigInt>], k: usize, l: usize) {
+        // b_k -= round(mu[k][l]) * b_l for l < k, rounding halves up, when |mu[k][l]| > 1/2
+        let (d, lambda) = (&self.d[l + 1], &mut self.lambda);
+        if (&lambda[k][l] * 2u32).abs() <= *d {
+            return;
+        }
+        let q = (&lambda[k][l] * 2u32 + d).div_floor(&(d * 2u32));
+        let (low, high) = b.split_at_mut(k);
+        for (x, y) in high[0].iter_mut().zip(&low[l]) {
+            *x -= &q * y;
+        }
+        let (low, high) = lambda.split_at_mut(k);
+        for (x, y) in high[0][..l].iter_mut().zip(&low[l][..l]) {
+            *x -= &q * y;
+        }
+        high[0][l] -= &q * d;
+    }
+
+    fn swap(&mut self, b: &mut [Vec<BigInt>], k: usize) {
+        // exchange b_k and b_(k - 1) and update the Gram-Schmidt data for the new order
+        b.swap(k, k - 1);
+        let (d, lambda) = (&mut self.d, &mut self.lambda);
+        let (low, high) = lambda.split_at_mut(k);
+        low[k - 1][..k - 1].swap_with_slice(&mut high[0][..k - 1]);
+        let l = lambda[k][k - 1].clone();
+        let new_d = (&d[k - 1] * &d[k + 1] + &l * &l) / &d[k];
+        for row in lambda[k + 1..].iter_mut() {
+            let t = row[k].clone();
+            row[k] = (&d[k + 1] * &row[k - 1] - &l * &t) / &d[k];
+            row[k - 1] = (&new_d * t + &l * &row[k]) / &d[k + 1];
+        }
+        d[k] = new_d;
+    }
+}
+
+pub fn lll(basis: &[Vec<BigRational>]) -> Vec<Vec<BigRational>> {
+    lll_with_delta(basis, &BigRational::new(99.into(), 100.into()))
+}
+
+pub fn lll_with_delta(basis: &[Vec<BigRational>], delta: &BigRational) -> Vec<Vec<BigRational>> {
+    // a basis of the same lattice that is size reduced, |mu[i][j]| <= 1/2, and satisfies the
+    // Lovasz condition |b*_k|^2 >= (delta - mu[k][k - 1]^2) |b*_(k - 1)|^2. delta must be in
+    // (1/4, 1]; closer to 1 gives shorter vectors for more work. scaling every vector by the same
+    // factor changes neither condition, so the work is done on the basis times the lcm of its
+    // denominators and scaled back at the end
+    assert!(
+        *delta > BigRational::new(1.into(), 4.into()) && *delta <= BigRational::one(),
+        "delta must be in (1/4, 1]."
+    );
+    let (delta_numer, delta_denom) = (delta.numer(), delta.denom());
+    let n = basis.len();
+    let scale = basis
+        .iter()
+        .flatten()
+        .fold(BigInt::one(), |acc, x| acc.lcm(x.denom()));
+    let mut b: Vec<Vec<BigInt>> = basis
+        .iter()
+        .map(|v| v.iter().map(|x| x.numer() * (&scale / x.denom())).collect())
+        .collect();
+    let mut gs = IntegralGramSchmidt::new(&b);
+    let mut k = 1;
+    while k < n {
+        gs.size_reduce(&mut b, k, k - 1);
+        // the Lovasz condition multiplied through by d[k]^2 and the denominator of delta
+        let l = &gs.lambda[k][k - 1];
+        let lhs = delta_denom * (&gs.d[k + 1] * &gs.d[k - 1] + l * l);
+        if lhs < delta_numer * &gs.d[k] * &gs.d[k] {
+            gs.swap(&mut b, k);
+            k = (k - 1).max(1);
+        } else {
+            for l in (0..k - 1).rev() {
+                gs.size_reduce(&mut b, k, l);
+            }
+            k += 1;
+        }
+    }
+    b.iter()
+        .map(|v| {
+            v.iter()
+                .map(|x| BigRational::new(x.clone(), scale.clone()))
+                .collect()
+        })
+        .collect()
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GramSchmidt {
+    // the orthogonalised vectors b*_i
+    pub orthogonal: Vec<Vec<BigRational>>,
+    // mu[i][j] = <b_i, b*_j> / <b*_j, b*_j> for j < i, and zero otherwise
+    pub mu: Vec<Vec<BigRational>>,
+}
+
+pub fn gram_schmidt(basis: &[Vec<BigRational>]) -> GramSchmidt {
+    // b*_i is b_i minus its projections onto the earlier b*_j, so b_i = b*_i + sum mu[i][j] b*_j
+    let n = basis.len();
+    let mut orthogonal: Vec<Vec<BigRational>> = Vec::with_capacity(n);
+    let mut mu = vec![vec![BigRational::zero(); n]; n];
+    for (i, b) in basis.iter().enumerate() {
+        let mut v = b.clone();
+        for j in 0..i {
+            mu[i][j] = dot(b, &orthogonal[j]) / dot(&orthogonal[j], &orthogonal[j]);
+            for (x, y) in v.iter_mut().zip(&orthogonal[j]) {
+                *x -= &mu[i][j] * y;
+            }
+        }
+        assert!(
+            v.iter().any(|x| !x.is_zero()),
+            "Basis vectors must be linearly independent."
+        );
+        orthogonal.push(v);
+    }
+    GramSchmidt { orthogonal, mu }
+}
+
+pub fn is_lll_reduced(basis: &[Vec<BigRational>], delta: &BigRational) -> bool {
+    let GramSchmidt { orthogonal, mu } = gram_schmidt(basis);
+    let half = BigRational::new(1.into(), 2.into());
+    let size_reduced = mu
+        .iter()
+        .enumerate()
+        .all(|(i, row)| row[..i].iter().all(|m| m.abs() <= half));
+    let lovasz = (1..basis.len()).all(|k| {
+        let m = &mu[k][k - 1];
+        dot(&orthogonal[k], &orthogonal[k])
+            >= (delta - m * m) * dot(&orthogonal[k - 1], &orthogonal[k - 1])
+    });
+    size_reduced && lovasz
+}
+
+pub fn babai_nearest_plane(basis: &[Vec<BigRational>], target: &[BigRational]) -> Vec<BigRational> {
+    babai_nearest_plane_with_delta(basis, target, &BigRational::new(99.into(), 100.into()))
+}
+
+pub fn babai_nearest_plane_with_delta(
+    basis: &[Vec<BigRational>],
+    target: &[BigRational],
+    delta: &BigRational,
+) -> Vec<BigRational> {
+    // a lattice vector close to target, found by reducing the basis with lll_with_delta and then
+    // rounding away the component of target along each b*_j from the last to the first. within
+    // 2^(n / 2) of the closest vector, and usually the closest itself when target is near the
+    // lattice
+    let reduced = lll_with_delta(basis, delta);
+    let GramSchmidt { orthogonal, .. } = gram_schmidt(&reduced);
+    let half = BigRational::new(1.into(), 2.into());
+    let mut remainder = target.to_vec();
+    for (b, b_star) in reduced.iter().zip(&orthogonal).rev() {
+        let c = (dot(&remainder, b_star) / dot(b_star, b_star) + &half).floor();
+        for (x, y) in remainder.iter_mut().zip(b) {
+            *x -= &c * y;
+        }
+    }
+    target.iter().zip(&remainder).map(|(t, r)| t - r).collect()
+}
+
+pub fn cvp_embedding(
+    basis: &[Vec<BigRational>],
+    target: &[BigRational],
+    weight: &BigRational,
+) -> Option<Vec<BigRational>> {
+    cvp_embedding_with_delta(
+        basis,
+        target,
+        weight,
+        &BigRational::new(99.into(), 100.into()),
+    )
+}
+
+pub fn cvp_embedding_with_delta(
+    basis: &[Vec<BigRational>],
+    target: &[BigRational],
+    weight: &BigRational,
+    delta: &BigRational,
+) -> Option<Vec<BigRational>> {
+    // Kannan's embedding: in the lattice spanned by (b_i, 0) and (target, weight), target minus
+    // the closest vector w gives the short vector (target - w, weight). weight should be around
+    // the expected distance to the lattice. None if no reduced vector ends in +-weight
+    assert!(weight.is_positive(), "weight must be positive.");
+    let mut embedded: Vec<Vec<BigRational>> = basis
+        .iter()
+        .map(|b| b.iter().cloned().chain([BigRational::zero()]).collect())
+        .collect();
+    embedded.push(target.iter().cloned().chain([weight.clone()]).collect());
+    let n = target.len();
+    let reduced = lll_with_delta(&embedded, delta);
+    reduced.into_iter().find_map(|row| {
+        let sign = if row[n] == *weight {
+            BigRational::one()
+        } else if row[n] == -weight {
+            -BigRational::one()
+        } else {
+            return None;
+        };
+        Some(
+            target
+                .iter()
+                .zip(&row[..n])
+                .map(|(t, x)| t - &sign * x)
+                .collect(),
+        )
+    })
+}
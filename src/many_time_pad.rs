@@ -0,0 +1,195 @@
+use crate::scoring::{LetterTableScorer, Scorer};
+use crate::xor_bytes;
+
+// attacks on ciphertexts that were all xored with the same keystream, like CTR under a fixed
+// nonce. xoring two of them cancels the keystream and leaves the xor of their plaintexts
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CribMatch {
+    // the crib sits at offset in the plaintext of one of first and second, and revealed is the
+    // plaintext of the other at the same offset. which is which takes context to tell
+    pub first: usize,
+    pub second: usize,
+    pub offset: usize,
+    pub revealed: Vec<u8>,
+    pub score: f64,
+}
+
+pub fn crib_drag(ciphertexts: &[Vec<u8>], crib: &[u8], n: usize) -> Vec<CribMatch> {
+    crib_drag_with(ciphertexts, crib, &LetterTableScorer, n)
+}
+
+pub fn crib_drag_with<S: Scorer + ?Sized>(
+    ciphertexts: &[Vec<u8>],
+    crib: &[u8],
+    scorer: &S,
+    n: usize,
+) -> Vec<CribMatch> {
+    // the n best placements of crib at every offset of every pair of ciphertexts, by the score of
+    // what each reveals. a placement that reveals readable text is probably right
+    assert!(!crib.is_empty(), "Crib must not be empty.");
+    let mut matches = vec![];
+    for (first, a) in ciphertexts.iter().enumerate() {
+        for (second, b) in ciphertexts.iter().enumerate().skip(first + 1) {
+            let combined = xor_bytes(&a[..a.len().min(b.len())], b);
+            for (offset, window) in combined.windows(crib.len()).enumerate() {
+                let revealed = xor_bytes(window, crib);
+                matches.push(CribMatch {
+                    first,
+                    second,
+                    offset,
+                    score: scorer.score(&revealed),
+                    revealed,
+                });
+            }
+        }
+    }
+    // stable, so ties keep the order of the pairs and offsets
+    matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+    matches.truncate(n);
+    matches
+}
+
+fn column(ciphertexts: &[Vec<u8>], position: usize) -> Vec<(usize, u8)> {
+    // (index, byte) for the ciphertexts long enough to have a byte at position
+    ciphertexts
+        .iter()
+        .enumerate()
+        .filter_map(|(i, c)| c.get(position).map(|&b| (i, b)))
+        .collect()
+}
+
+fn max_len(ciphertexts: &[Vec<u8>]) -> usize {
+    ciphertexts.iter().map(|c| c.len()).max().unwrap_or(0)
+}
+
+pub fn detect_spaces(ciphertexts: &[Vec<u8>], min_fraction: f64) -> Vec<Vec<bool>> {
+    // a space xored with a letter flips its case, and two letters never xor to a letter, so a
+    // byte that xors to a letter against at least min_fraction of the other ciphertexts at that
+    // position is probably a space. positions only one ciphertext reaches are never flagged
+    ciphertexts
+        .iter()
+        .enumerate()
+        .map(|(i, ciphertext)| {
+            ciphertext
+                .iter()
+                .enumerate()
+                .map(|(position, &a)| {
+                    let others: Vec<u8> = column(ciphertexts, position)
+                        .into_iter()
+                        .filter(|&(j, _)| j != i)
+                        .map(|(_, b)| b)
+                        .collect();
+                    let letters = others
+                        .iter()
+                        .filter(|&&b| (a ^ b).is_ascii_alphabetic())
+                        .count();
+                    !others.is_empty() && letters as f64 >= min_fraction * others.len() as f64
+                })
+                .collect()
+        })
+        .collect()
+}
+
+pub fn solve_columns(ciphertexts: &[Vec<u8>]) -> Vec<u8> {
+    solve_columns_with(ciphertexts, &LetterTableScorer)
+}
+
+pub fn solve_columns_with<S: Scorer + ?Sized>(ciphertexts: &[Vec<u8>], scorer: &S) -> Vec<u8> {
+    // a keystream as long as the longest ciphertext. every pair of ciphertexts that xors to a
+    // letter at a position votes for the two keystream bytes that would make one of them a
+    // space, and the byte with the most votes wins, with the score of the whole column
+    // decrypted breaking ties. a column with no letter pairs falls to the scorer alone
+    (0..max_len(ciphertexts))
+        .map(|position| {
+            let column = column(ciphertexts, position);
+            let mut votes = [0u32; 256];
+            for (k, &(_, a)) in column.iter().enumerate() {
+                for &(_, b) in &column[k + 1..] {
+                    if (a ^ b).is_ascii_alphabetic() {
+                        votes[(a ^ b' ') as usize] += 1;
+                        votes[(b ^ b' ') as usize] += 1;
+                    }
+                }
+            }
+            let bytes: Vec<u8> = column.iter().map(|&(_, b)| b).collect();
+            let score = |key: u8| scorer.score(&xor_bytes(&bytes, &[key]));
+            (0..=255u8)
+                .map(|key| (votes[key as usize], score(key), key))
+                .max_by(|a, b| a.0.cmp(&b.0).then(a.1.total_cmp(&b.1)).then(b.2.cmp(&a.2)))
+                .unwrap()
+                .2
+        })
+        .collect()
+}
+
+// a partly known keystream shared by a set of ciphertexts, which is filled in a guess at a time
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ManyTimePad {
+    pub ciphertexts: Vec<Vec<u8>>,
+    // one entry per position of the longest ciphertext, None where the byte is unknown
+    pub keystream: Vec<Option<u8>>,
+}
+
+impl ManyTimePad {
+    pub fn new(ciphertexts: Vec<Vec<u8>>) -> Self {
+        let keystream = vec![None; max_len(&ciphertexts)];
+        ManyTimePad {
+            ciphertexts,
+            keystream,
+        }
+    }
+
+    pub fn guess(&mut self, index: usize, offset: usize, plaintext: &[u8]) {
+        // confirms that ciphertext index decrypts to plaintext at offset, which fixes those
+        // keystream bytes for every other ciphertext too
+        let ciphertext = &self.ciphertexts[index];
+        assert!(
+            offset + plaintext.len() <= ciphertext.len(),
+            "Guess runs past the end of the ciphertext."
+        );
+        for (i, p) in plaintext.iter().enumerate() {
+            self.keystream[offset + i] = Some(ciphertext[offset + i] ^ p);
+        }
+    }
+
+    pub fn forget(&mut self, offset: usize, len: usize) {
+        // takes back a wrong guess
+        self.keystream[offset..offset + len].fill(None);
+    }
+
+    pub fn apply_spaces(&mut self, min_fraction: f64) {
+        // fills unknown keystream bytes from the spaces detect_spaces finds, trusting the first
+        // ciphertext flagged at each position
+        let spaces = detect_spaces(&self.ciphertexts, min_fraction);
+        for (ciphertext, flags) in self.ciphertexts.iter().zip(&spaces) {
+            for (position, _) in flags.iter().enumerate().filter(|&(_, &flag)| flag) {
+                self.keystream[position].get_or_insert(ciphertext[position] ^ b' ');
+            }
+        }
+    }
+
+    pub fn solve_with<S: Scorer + ?Sized>(&mut self, scorer: &S) {
+        // fills every unknown keystream byte from solve_columns_with, keeping confirmed ones
+        let solved = solve_columns_with(&self.ciphertexts, scorer);
+        for (known, solved) in self.keystream.iter_mut().zip(solved) {
+            known.get_or_insert(solved);
+        }
+    }
+
+    pub fn plaintext(&self, index: usize, unknown: u8) -> Vec<u8> {
+        // ciphertext index decrypted as far as the keystream is known, with unknown standing in
+        // for the rest
+        self.ciphertexts[index]
+            .iter()
+            .zip(&self.keystream)
+            .map(|(c, k)| k.map_or(unknown, |k| c ^ k))
+            .collect()
+    }
+
+    pub fn plaintexts(&self, unknown: u8) -> Vec<Vec<u8>> {
+        (0..self.ciphertexts.len())
+            .map(|index| self.plaintext(index, unknown))
+            .collect()
+    }
+}
@@ -0,0 +1,377 @@
+use crate::encrypt_aes_128_block;
+use std::collections::HashMap;
+
+// a deliberately weak Merkle-Damgard hash: the chaining state (padded with zeros) is the AES key,
+// the message block is the plaintext and the output is truncated back down to the state size
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WeakHash {
+    pub state_bits: u32,
+    // distinguishes otherwise identical hashes, e.g. the cheap and expensive halves of a cascade
+    pub iv_seed: u8,
+}
+
+impl WeakHash {
+    pub fn new(state_bits: u32) -> Self {
+        assert!(
+            state_bits.is_multiple_of(8) && (8..=128).contains(&state_bits),
+            "State must be a whole number of bytes, up to 128 bits."
+        );
+        WeakHash {
+            state_bits,
+            iv_seed: 0,
+        }
+    }
+
+    pub fn with_iv_seed(state_bits: u32, iv_seed: u8) -> Self {
+        WeakHash {
+            iv_seed,
+            ..WeakHash::new(state_bits)
+        }
+    }
+
+    pub fn state_len(&self) -> usize {
+        self.state_bits as usize / 8
+    }
+
+    pub fn initial_state(&self) -> Vec<u8> {
+        (0..self.state_len())
+            .map(|i| (i as u8).wrapping_mul(0x3b) ^ 0xa5 ^ self.iv_seed)
+            .collect()
+    }
+
+    pub fn compress(&self, state: &[u8], block: &[u8]) -> Vec<u8> {
+        assert_eq!(block.len(), 16, "Blocks are 16 bytes.");
+        let key = [state, &vec![0u8; 16 - state.len()]].concat();
+        let mut encrypted = encrypt_aes_128_block(block, &key);
+        encrypted.truncate(self.state_len());
+        encrypted
+    }
+
+    pub fn hash_blocks(&self, state: &[u8], message: &[u8]) -> Vec<u8> {
+        // no padding, so message has to be block aligned
+        assert_eq!(message.len() % 16, 0, "Message must be block aligned.");
+        message
+            .chunks(16)
+            .fold(state.to_vec(), |state, block| self.compress(&state, block))
+    }
+
+    pub fn pad(&self, message_len: usize) -> Vec<u8> {
+        // MD strengthening: 0x80, zeros, then the message length in bits as a big endian u64
+        let zeros = (16 - (message_len + 9) % 16) % 16;
+        [
+            vec![0x80],
+            vec![0u8; zeros],
+            ((message_len as u64) * 8).to_be_bytes().to_vec(),
+        ]
+        .concat()
+    }
+
+    pub fn hash(&self, message: &[u8]) -> Vec<u8> {
+        let padded = [message.to_vec(), self.pad(message.len())].concat();
+        self.hash_blocks(&self.initial_state(), &padded)
+    }
+}
+
+pub fn block_from_counter(tag: u64, counter: u64) -> Vec<u8> {
+    // cheap source of distinct blocks for the searches below
+    [tag.to_be_bytes(), counter.to_be_bytes()].concat()
+}
+
+pub fn find_block_collision(
+    hash: &WeakHash,
+    state: &[u8],
+    tag: u64,
+) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+    // birthday search for two blocks that take state to the same place.
+    // returns (block_a, block_b, next_state)
+    let mut seen: HashMap<Vec<u8>, Vec<u8>> = HashMap::new();
+    for counter in 0u64.. {
+        let block = block_from_counter(tag, counter);
+        let next = hash.compress(state, &block);
+        if let Some(other) = seen.insert(next.clone(), block.clone()) {
+            return (other, block, next);
+        }
+    }
+    unreachable!()
+}
+
+pub fn find_cross_collision(
+    hash: &WeakHash,
+    state_a: &[u8],
+    state_b: &[u8],
+    tag: u64,
+) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+    // birthday search for a block from state_a and a block from state_b that meet, alternating
+    // between the two sides. returns (block_a, block_b, next_state)
+    let mut seen_a: HashMap<Vec<u8>, Vec<u8>> = HashMap::new();
+    let mut seen_b: HashMap<Vec<u8>, Vec<u8>> = HashMap::new();
+    for counter in 0u64.. {
+        let block = block_from_counter(tag, counter);
+        let next_a = hash.compress(state_a, &block);
+        if let Some(block_b) = seen_b.get(&next_a) {
+            return (block, block_b.clone(), next_a);
+        }
+        seen_a.insert(next_a, block.clone());
+        let next_b = hash.compress(state_b, &block);
+        if let Some(block_a) = seen_a.get(&next_b) {
+            return (block_a.clone(), block, next_b);
+        }
+        seen_b.insert(next_b, block);
+    }
+    unreachable!()
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Multicollision {
+    // one colliding pair of blocks per step; picking either block at every step gives 2^t
+    // messages of t blocks that all end in final_state
+    pub pairs: Vec<(Vec<u8>, Vec<u8>)>,
+    pub final_state: Vec<u8>,
+}
+
+impl Multicollision {
+    pub fn message_count(&self) -> usize {
+        1 << self.pairs.len()
+    }
+
+    pub fn message(&self, index: usize) -> Vec<u8> {
+        // bit i of index picks the block for step i
+        self.pairs
+            .iter()
+            .enumerate()
+            .flat_map(|(i, (a, b))| if index >> i & 1 == 0 { a } else { b }.clone())
+            .collect()
+    }
+
+    pub fn messages(&self) -> impl Iterator<Item = Vec<u8>> + '_ {
+        (0..self.message_count()).map(|i| self.message(i))
+    }
+}
+
+pub fn extend_joux_multicollision(hash: &WeakHash, multicollision: &mut Multicollision) {
+    // one more birthday search doubles the number of colliding messages
+    let tag = multicollision.pairs.len() as u64;
+    let (a, b, next) = find_block_collision(hash, &multicollision.final_state, tag);
+    multicollision.pairs.push((a, b));
+    multicollision.final_state = next;
+}
+
+pub fn joux_multicollision(hash: &WeakHash, state: &[u8], t: usize) -> Multicollision {
+    // 2^t colliding messages for the price of t collisions
+    let mut multicollision = Multicollision {
+        pairs: vec![],
+        final_state: state.to_vec(),
+    };
+    for _ in 0..t {
+        extend_joux_multicollision(hash, &mut multicollision);
+    }
+    multicollision
+}
+
+pub fn cascade_hash(cheap: &WeakHash, expensive: &WeakHash, message: &[u8]) -> Vec<u8> {
+    [cheap.hash(message), expensive.hash(message)].concat()
+}
+
+pub fn find_cascade_collision(cheap: &WeakHash, expensive: &WeakHash) -> (Vec<u8>, Vec<u8>) {
+    // every message in a Joux multicollision of the cheap hash collides under it, so enough of
+    // them will contain an expensive collision by the birthday bound. Costs about
+    // (expensive bits / 2) cheap collisions plus 2^(expensive bits / 2) expensive hashes instead
+    // of 2^((cheap bits + expensive bits) / 2).
+    let mut multicollision = joux_multicollision(
+        cheap,
+        &cheap.initial_state(),
+        expensive.state_bits as usize / 2,
+    );
+    loop {
+        let mut seen: HashMap<Vec<u8>, Vec<u8>> = HashMap::new();
+        for message in multicollision.messages() {
+            let digest = expensive.hash(&message);
+            if let Some(other) = seen.insert(digest, message.clone()) {
+                return (other, message);
+            }
+        }
+        extend_joux_multicollision(cheap, &mut multicollision);
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExpandableMessage {
+    // for step i (counting down from k), a single block and a message of 2^(i-1) + 1 blocks that
+    // collide
+    pub pieces: Vec<(Vec<u8>, Vec<u8>)>,
+    pub final_state: Vec<u8>,
+}
+
+impl ExpandableMessage {
+    pub fn min_blocks(&self) -> usize {
+        self.pieces.len()
+    }
+
+    pub fn max_blocks(&self) -> usize {
+        self.pieces.len() + (1 << self.pieces.len()) - 1
+    }
+
+    pub fn message(&self, blocks: usize) -> Vec<u8> {
+        // a message of exactly blocks blocks ending in final_state
+        assert!(
+            (self.min_blocks()..=self.max_blocks()).contains(&blocks),
+            "Length out of range."
+        );
+        let extra = blocks - self.min_blocks();
+        let k = self.pieces.len();
+        self.pieces
+            .iter()
+            .enumerate()
+            .flat_map(|(i, (short, long))| {
+                // pieces[i] is step k - i, whose long version adds 2^(k - i - 1) blocks
+                if extra >> (k - i - 1) & 1 == 1 {
+                    long.clone()
+                } else {
+                    short.clone()
+                }
+            })
+            .collect()
+    }
+}
+
+pub fn expandable_message(hash: &WeakHash, state: &[u8], k: usize) -> ExpandableMessage {
+    // Kelsey-Schneier: k collisions between a 1 block and a 2^(i-1) + 1 block message
+    let mut pieces: Vec<(Vec<u8>, Vec<u8>)> = vec![];
+    let mut state = state.to_vec();
+    for i in (1..=k).rev() {
+        let tag = 1 << 32 | i as u64;
+        let dummy: Vec<u8> = (0..1u64 << (i - 1))
+            .flat_map(|n| block_from_counter(u64::MAX, n))
+            .collect();
+        let dummy_state = hash.hash_blocks(&state, &dummy);
+        let (short, last, next) = find_cross_collision(hash, &state, &dummy_state, tag);
+        pieces.push((short, [dummy, last].concat()));
+        state = next;
+    }
+    ExpandableMessage {
+        pieces,
+        final_state: state,
+    }
+}
+
+pub fn second_preimage(hash: &WeakHash, message: &[u8]) -> Option<Vec<u8>> {
+    // long message second preimage: bridge from an expandable message into one of message's
+    // intermediate states, then expand to exactly the original length so the padding matches.
+    // message has to be block aligned; returns None if it is too short to attack.
+    assert_eq!(message.len() % 16, 0, "Message must be block aligned.");
+    let blocks = message.len() / 16;
+    let k = (usize::BITS - blocks.leading_zeros()) as usize - 1;
+    if k < 1 {
+        return None;
+    }
+    let expandable = expandable_message(hash, &hash.initial_state(), k);
+    // state after j blocks -> j, for every j the expandable message can reach with one more
+    // block to spare for the bridge
+    let mut intermediate: HashMap<Vec<u8>, usize> = HashMap::new();
+    let mut state = hash.initial_state();
+    for (j, block) in message.chunks(16).enumerate() {
+        state = hash.compress(&state, block);
+        let reached = j + 1;
+        if (expandable.min_blocks() + 1..=expandable.max_blocks() + 1).contains(&reached) {
+            intermediate.entry(state.clone()).or_insert(reached);
+        }
+    }
+    if intermediate.is_empty() {
+        return None;
+    }
+    for counter in 0u64.. {
+        let bridge = block_from_counter(2 << 32, counter);
+        let next = hash.compress(&expandable.final_state, &bridge);
+        if let Some(&j) = intermediate.get(&next) {
+            let forged = [
+                expandable.message(j - 1),
+                bridge,
+                message[j * 16..].to_vec(),
+            ]
+            .concat();
+            if forged != message {
+                return Some(forged);
+            }
+        }
+    }
+    unreachable!()
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiamondStructure {
+    // levels[0] holds the 2^k leaf states and every level after that the states they merge
+    // into; blocks[d][i] takes levels[d][i] to its parent levels[d + 1][i / 2]
+    pub levels: Vec<Vec<Vec<u8>>>,
+    pub blocks: Vec<Vec<Vec<u8>>>,
+}
+
+pub fn build_diamond_structure(hash: &WeakHash, k: usize) -> DiamondStructure {
+    // Kelsey-Kohno herding: pair up 2^k states and find a block for each side of every pair that
+    // makes them meet, until a single root state is left
+    let leaves: Vec<Vec<u8>> = (0..1u64 << k)
+        .map(|n| hash.compress(&hash.initial_state(), &block_from_counter(3 << 32, n)))
+        .collect();
+    let mut levels: Vec<Vec<Vec<u8>>> = vec![leaves];
+    let mut blocks: Vec<Vec<Vec<u8>>> = vec![];
+    for depth in 0..k {
+        let current = levels.last().unwrap();
+        let mut next_level: Vec<Vec<u8>> = vec![];
+        let mut level_blocks: Vec<Vec<u8>> = vec![];
+        for (pair, states) in current.chunks(2).enumerate() {
+            let tag = 4 << 32 | (depth as u64) << 24 | pair as u64;
+            let (a, b, next) = if states[0] == states[1] {
+                let block = block_from_counter(tag, 0);
+                let next = hash.compress(&states[0], &block);
+                (block.clone(), block, next)
+            } else {
+                find_cross_collision(hash, &states[0], &states[1], tag)
+            };
+            level_blocks.push(a);
+            level_blocks.push(b);
+            next_level.push(next);
+        }
+        blocks.push(level_blocks);
+        levels.push(next_level);
+    }
+    DiamondStructure { levels, blocks }
+}
+
+impl DiamondStructure {
+    pub fn depth(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn root(&self) -> &[u8] {
+        &self.levels.last().unwrap()[0]
+    }
+
+    pub fn prediction(&self, hash: &WeakHash, prefix_blocks: usize) -> Vec<u8> {
+        // the hash to publish in advance for messages of prefix_blocks blocks, followed by the
+        // linking block and the path up the diamond
+        let message_len = (prefix_blocks + 1 + self.depth()) * 16;
+        hash.hash_blocks(self.root(), &hash.pad(message_len))
+    }
+
+    pub fn herd(&self, hash: &WeakHash, prefix: &[u8]) -> Vec<u8> {
+        // prefix (block aligned) || linking block into some leaf || path from that leaf to the
+        // root, which hashes to prediction(prefix.len() / 16)
+        assert_eq!(prefix.len() % 16, 0, "Prefix must be block aligned.");
+        let leaf_index: HashMap<&Vec<u8>, usize> = self.levels[0]
+            .iter()
+            .enumerate()
+            .map(|(i, leaf)| (leaf, i))
+            .collect();
+        let state = hash.hash_blocks(&hash.initial_state(), prefix);
+        for counter in 0u64.. {
+            let link = block_from_counter(5 << 32, counter);
+            let next = hash.compress(&state, &link);
+            if let Some(&leaf) = leaf_index.get(&next) {
+                let path: Vec<u8> = (0..self.depth())
+                    .flat_map(|depth| self.blocks[depth][leaf >> depth].clone())
+                    .collect();
+                return [prefix.to_vec(), link, path].concat();
+            }
+        }
+        unreachable!()
+    }
+}
@@ -0,0 +1,508 @@
+use crate::bignum::{biguint_to_bytes_padded, crt, generate_prime, random_biguint_range};
+use num_bigint::BigUint;
+use num_integer::Integer;
+use num_traits::{One, Zero};
+use rand::Rng;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RsaPublicKey {
+    pub e: BigUint,
+    pub n: BigUint,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RsaPrivateKey {
+    pub d: BigUint,
+    pub n: BigUint,
+}
+
+pub fn generate_rsa_keypair<R: Rng + ?Sized>(
+    modulus_bits: u64,
+    e: &BigUint,
+    rng: &mut R,
+) -> (RsaPublicKey, RsaPrivateKey) {
+    let prime_bits = modulus_bits / 2;
+    loop {
+        let p = generate_prime(prime_bits, rng);
+        let q = generate_prime(prime_bits, rng);
+        if p == q {
+            continue;
+        }
+        let et = (&p - 1u32) * (&q - 1u32);
+        // e has to be invertible mod et, which for e = 3 rules out most primes
+        if !e.gcd(&et).is_one() {
+            continue;
+        }
+        let d = e.modinv(&et).unwrap();
+        let n = p * q;
+        return (
+            RsaPublicKey {
+                e: e.clone(),
+                n: n.clone(),
+            },
+            RsaPrivateKey { d, n },
+        );
+    }
+}
+
+pub fn rsa_encrypt_int(m: &BigUint, public_key: &RsaPublicKey) -> BigUint {
+    assert!(m < &public_key.n, "Message is too large for the modulus.");
+    m.modpow(&public_key.e, &public_key.n)
+}
+
+pub fn rsa_decrypt_int(c: &BigUint, private_key: &RsaPrivateKey) -> BigUint {
+    c.modpow(&private_key.d, &private_key.n)
+}
+
+pub fn rsa_encrypt(input_bytes: &[u8], public_key: &RsaPublicKey) -> Vec<u8> {
+    let m = BigUint::from_bytes_be(input_bytes);
+    rsa_encrypt_int(&m, public_key).to_bytes_be()
+}
+
+pub fn rsa_decrypt(input_bytes: &[u8], private_key: &RsaPrivateKey) -> Vec<u8> {
+    // textbook RSA works on integers, so leading null bytes of the plaintext are not recovered
+    let c = BigUint::from_bytes_be(input_bytes);
+    rsa_decrypt_int(&c, private_key).to_bytes_be()
+}
+
+pub fn rsa_broadcast_attack(ciphertexts_keys: &[(Vec<u8>, RsaPublicKey)]) -> Vec<u8> {
+    // the same message encrypted under e distinct moduli with public exponent e: CRT gives
+    // m^e mod n_1 * ... * n_e, and since m^e is smaller than that product it is just m^e
+    let Some((_, first_key)) = ciphertexts_keys.first() else {
+        panic!("Need at least e ciphertexts.");
+    };
+    let e = &first_key.e;
+    let e_u32: u32 = e.try_into().expect("Public exponent is too large.");
+    assert!(
+        ciphertexts_keys.iter().all(|(_, key)| &key.e == e),
+        "All keys must share the same public exponent."
+    );
+    assert!(
+        ciphertexts_keys.len() >= e_u32 as usize,
+        "Need at least e ciphertexts."
+    );
+    let (residues, moduli): (Vec<BigUint>, Vec<BigUint>) = ciphertexts_keys
+        .iter()
+        .take(e_u32 as usize)
+        .map(|(c, key)| (BigUint::from_bytes_be(c), key.n.clone()))
+        .unzip();
+    let (m_e, _) = crt(&residues, &moduli);
+    m_e.nth_root(e_u32).to_bytes_be()
+}
+
+pub struct DecryptOnceOracle {
+    private_key: RsaPrivateKey,
+    seen: HashSet<Vec<u8>>,
+}
+
+impl DecryptOnceOracle {
+    pub fn new(private_key: RsaPrivateKey) -> Self {
+        DecryptOnceOracle {
+            private_key,
+            seen: HashSet::new(),
+        }
+    }
+
+    pub fn decrypt(&mut self, ciphertext: &[u8]) -> Option<Vec<u8>> {
+        // refuses any ciphertext it has already decrypted, including ones that only differ by a
+        // multiple of n
+        let normalised = (BigUint::from_bytes_be(ciphertext) % &self.private_key.n).to_bytes_be();
+        if !self.seen.insert(normalised) {
+            return None;
+        }
+        Some(rsa_decrypt(ciphertext, &self.private_key))
+    }
+}
+
+pub fn recover_unpadded_message<R: Rng + ?Sized>(
+    ciphertext: &[u8],
+    public_key: &RsaPublicKey,
+    oracle: &mut DecryptOnceOracle,
+    rng: &mut R,
+) -> Vec<u8> {
+    // submit C' = S^e * C, then P = P' / S mod N
+    let n = &public_key.n;
+    let c = BigUint::from_bytes_be(ciphertext);
+    loop {
+        let s = random_biguint_range(&BigUint::from(2u32), n, rng);
+        let Some(s_inv) = s.modinv(n) else {
+            continue;
+        };
+        let c_prime = (s.modpow(&public_key.e, n) * &c) % n;
+        let Some(p_prime) = oracle.decrypt(&c_prime.to_bytes_be()) else {
+            continue;
+        };
+        let p = (BigUint::from_bytes_be(&p_prime) * s_inv) % n;
+        return p.to_bytes_be();
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Sha1,
+    Sha256,
+}
+
+impl HashAlgorithm {
+    pub fn digest(&self, message: &[u8]) -> Vec<u8> {
+        match self {
+            HashAlgorithm::Sha1 => Sha1::digest(message).to_vec(),
+            HashAlgorithm::Sha256 => Sha256::digest(message).to_vec(),
+        }
+    }
+
+    pub fn digest_info_prefix(&self) -> &'static [u8] {
+        // DER encoding of DigestInfo up to (not including) the hash itself, from RFC 8017
+        match self {
+            HashAlgorithm::Sha1 => &[
+                0x30, 0x21, 0x30, 0x09, 0x06, 0x05, 0x2b, 0x0e, 0x03, 0x02, 0x1a, 0x05, 0x00, 0x04,
+                0x14,
+            ],
+            HashAlgorithm::Sha256 => &[
+                0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+                0x01, 0x05, 0x00, 0x04, 0x20,
+            ],
+        }
+    }
+
+    pub fn digest_info(&self, message: &[u8]) -> Vec<u8> {
+        [self.digest_info_prefix().to_vec(), self.digest(message)].concat()
+    }
+}
+
+pub fn modulus_len(n: &BigUint) -> usize {
+    n.bits().div_ceil(8) as usize
+}
+
+pub fn pkcs1_v15_encode_signature(
+    message: &[u8],
+    hash: HashAlgorithm,
+    len: usize,
+) -> Option<Vec<u8>> {
+    // 00 01 FF .. FF 00 DigestInfo, with at least 8 bytes of FF. None if len is too short to
+    // hold that for this hash
+    let digest_info = hash.digest_info(message);
+    let padding_len = len
+        .checked_sub(digest_info.len() + 3)
+        .filter(|&padding| padding >= 8)?;
+    Some(
+        [
+            vec![0x00, 0x01],
+            vec![0xff; padding_len],
+            vec![0x00],
+            digest_info,
+        ]
+        .concat(),
+    )
+}
+
+pub fn rsa_sign_pkcs1_v15(
+    message: &[u8],
+    hash: HashAlgorithm,
+    private_key: &RsaPrivateKey,
+) -> Option<Vec<u8>> {
+    // None if the modulus is too short for this hash, just as no signature verifies under it
+    let len = modulus_len(&private_key.n);
+    let encoded = BigUint::from_bytes_be(&pkcs1_v15_encode_signature(message, hash, len)?);
+    Some(biguint_to_bytes_padded(
+        &rsa_decrypt_int(&encoded, private_key),
+        len,
+    ))
+}
+
+fn rsa_open_signature(signature: &[u8], public_key: &RsaPublicKey) -> Option<Vec<u8>> {
+    let len = modulus_len(&public_key.n);
+    let s = BigUint::from_bytes_be(signature);
+    if signature.len() != len || s >= public_key.n {
+        return None;
+    }
+    Some(biguint_to_bytes_padded(
+        &s.modpow(&public_key.e, &public_key.n),
+        len,
+    ))
+}
+
+pub fn rsa_verify_pkcs1_v15(
+    message: &[u8],
+    signature: &[u8],
+    hash: HashAlgorithm,
+    public_key: &RsaPublicKey,
+) -> bool {
+    // re-encodes the expected block and compares all of it. a modulus too short to hold the
+    // block for this hash verifies nothing
+    match rsa_open_signature(signature, public_key) {
+        Some(opened) => pkcs1_v15_encode_signature(message, hash, opened.len()) == Some(opened),
+        None => false,
+    }
+}
+
+pub fn rsa_verify_pkcs1_v15_sloppy(
+    message: &[u8],
+    signature: &[u8],
+    hash: HashAlgorithm,
+    public_key: &RsaPublicKey,
+) -> bool {
+    // INSECURE: parses the block left to right and never checks that the DigestInfo ends at the
+    // end of the block, which is what makes forge_pkcs1_v15_signature_e3 work
+    let Some(opened) = rsa_open_signature(signature, public_key) else {
+        return false;
+    };
+    if opened.len() < 2 || opened[0] != 0x00 || opened[1] != 0x01 {
+        return false;
+    }
+    let padding_len = opened[2..].iter().take_while(|&&b| b == 0xff).count();
+    let rest = &opened[2 + padding_len..];
+    if padding_len == 0 || rest.first() != Some(&0x00) {
+        return false;
+    }
+    rest[1..].starts_with(&hash.digest_info(message))
+}
+
+pub fn forge_pkcs1_v15_signature_e3(
+    message: &[u8],
+    hash: HashAlgorithm,
+    public_key: &RsaPublicKey,
+) -> Option<Vec<u8>> {
+    // Bleichenbacher's 2006 forgery: put the DigestInfo high in the block, fill the rest with
+    // garbage and take a cube root. Uses as many FF bytes as still leave room for the garbage.
+    assert_eq!(public_key.e, BigUint::from(3u32), "Forgery needs e = 3.");
+    let len = modulus_len(&public_key.n);
+    let digest_info = hash.digest_info(message);
+    let max_padding_len = len.checked_sub(digest_info.len() + 3)?;
+    for padding_len in (1..=max_padding_len).rev() {
+        let prefix = [
+            vec![0x00, 0x01],
+            vec![0xff; padding_len],
+            vec![0x00],
+            digest_info.clone(),
+        ]
+        .concat();
+        let garbage_len = len - prefix.len();
+        let low = BigUint::from_bytes_be(&[prefix.clone(), vec![0x00; garbage_len]].concat());
+        let high = BigUint::from_bytes_be(&[prefix, vec![0xff; garbage_len]].concat());
+        // the floor of the cube root of the top of the range cubes to something inside it
+        // whenever the range is wide enough
+        let root = high.cbrt();
+        if root.pow(3) >= low {
+            return Some(biguint_to_bytes_padded(&root, len));
+        }
+    }
+    None
+}
+
+pub struct RsaParityOracle {
+    private_key: RsaPrivateKey,
+}
+
+impl RsaParityOracle {
+    pub fn new(private_key: RsaPrivateKey) -> Self {
+        RsaParityOracle { private_key }
+    }
+
+    pub fn is_even(&self, ciphertext: &BigUint) -> bool {
+        rsa_decrypt_int(ciphertext, &self.private_key).is_even()
+    }
+}
+
+pub fn rsa_parity_oracle_attack<O, P>(
+    ciphertext: &BigUint,
+    public_key: &RsaPublicKey,
+    mut is_even: O,
+    mut progress: P,
+) -> BigUint
+where
+    O: FnMut(&BigUint) -> bool,
+    P: FnMut(&BigUint),
+{
+    // after i doublings the plaintext lies in [t * n / 2^i, (t + 1) * n / 2^i), and the parity of
+    // 2^i * m mod n tells us which half of that interval it is in. progress gets the current
+    // upper bound after every step.
+    let n = &public_key.n;
+    let doubler = BigUint::from(2u32).modpow(&public_key.e, n);
+    let mut c = ciphertext.clone();
+    let mut t = BigUint::zero();
+    let steps = n.bits();
+    for i in 1..=steps {
+        c = (c * &doubler) % n;
+        t <<= 1;
+        if !is_even(&c) {
+            t += 1u32;
+        }
+        progress(&(((&t + 1u32) * n) >> i));
+    }
+    // the interval is now narrower than 1, so its lower bound rounded up is the plaintext
+    (t * n + (BigUint::one() << steps) - 1u32) >> steps
+}
+
+pub fn pkcs1_v15_pad_encryption<R: Rng + ?Sized>(
+    message: &[u8],
+    len: usize,
+    rng: &mut R,
+) -> Vec<u8> {
+    // 00 02 PS 00 M, where PS is at least 8 random non-zero bytes
+    assert!(message.len() + 11 <= len, "Message is too long.");
+    let padding: Vec<u8> = (0..len - message.len() - 3)
+        .map(|_| rng.random_range(1..=255u8))
+        .collect();
+    [vec![0x00, 0x02], padding, vec![0x00], message.to_vec()].concat()
+}
+
+pub fn pkcs1_v15_unpad_encryption(padded: &[u8]) -> Option<Vec<u8>> {
+    if padded.len() < 11 || padded[0] != 0x00 || padded[1] != 0x02 {
+        return None;
+    }
+    let separator = padded[2..].iter().position(|&b| b == 0x00)? + 2;
+    if separator < 10 {
+        return None;
+    }
+    Some(padded[separator + 1..].to_vec())
+}
+
+pub fn rsa_encrypt_pkcs1_v15<R: Rng + ?Sized>(
+    message: &[u8],
+    public_key: &RsaPublicKey,
+    rng: &mut R,
+) -> Vec<u8> {
+    let len = modulus_len(&public_key.n);
+    let padded = BigUint::from_bytes_be(&pkcs1_v15_pad_encryption(message, len, rng));
+    biguint_to_bytes_padded(&rsa_encrypt_int(&padded, public_key), len)
+}
+
+pub fn rsa_decrypt_pkcs1_v15(ciphertext: &[u8], private_key: &RsaPrivateKey) -> Option<Vec<u8>> {
+    let len = modulus_len(&private_key.n);
+    let c = BigUint::from_bytes_be(ciphertext);
+    let padded = biguint_to_bytes_padded(&rsa_decrypt_int(&c, private_key), len);
+    pkcs1_v15_unpad_encryption(&padded)
+}
+
+pub struct Pkcs1ConformanceOracle {
+    private_key: RsaPrivateKey,
+    len: usize,
+}
+
+impl Pkcs1ConformanceOracle {
+    pub fn new(private_key: RsaPrivateKey) -> Self {
+        let len = modulus_len(&private_key.n);
+        Pkcs1ConformanceOracle { private_key, len }
+    }
+
+    pub fn is_conforming(&self, ciphertext: &BigUint) -> bool {
+        // only looks at the leading 00 02, like the weakest real-world oracles
+        let m = rsa_decrypt_int(ciphertext, &self.private_key);
+        m >> (8 * (self.len - 2)) == BigUint::from(2u32)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bleichenbacher98Step {
+    // 2a, 2b and 2c from the paper, reported once an s has been found
+    SearchStart,
+    SearchMultipleIntervals,
+    SearchSingleInterval,
+}
+
+fn merge_intervals(mut intervals: Vec<(BigUint, BigUint)>) -> Vec<(BigUint, BigUint)> {
+    intervals.sort();
+    let mut merged: Vec<(BigUint, BigUint)> = vec![];
+    for (a, b) in intervals {
+        match merged.last_mut() {
+            Some((_, last_b)) if a <= &*last_b + 1u32 => {
+                if b > *last_b {
+                    *last_b = b;
+                }
+            }
+            _ => merged.push((a, b)),
+        }
+    }
+    merged
+}
+
+pub fn bleichenbacher_98_attack<O, P>(
+    ciphertext: &BigUint,
+    public_key: &RsaPublicKey,
+    mut is_conforming: O,
+    mut progress: P,
+) -> Vec<u8>
+where
+    O: FnMut(&BigUint) -> bool,
+    P: FnMut(Bleichenbacher98Step, &BigUint, &[(BigUint, BigUint)]),
+{
+    // ciphertext must itself be PKCS conforming, so step 1 (blinding) is skipped with s0 = 1.
+    // returns the full padded plaintext block. progress gets the step just completed, the s it
+    // found and the narrowed set of intervals.
+    let n = &public_key.n;
+    let e = &public_key.e;
+    let len = modulus_len(&public_key.n);
+    let b = BigUint::one() << (8 * (len - 2));
+    let two_b: BigUint = &b * 2u32;
+    let three_b: BigUint = &b * 3u32;
+    let conforms =
+        |s: &BigUint, is_conforming: &mut O| is_conforming(&((ciphertext * s.modpow(e, n)) % n));
+
+    let mut intervals: Vec<(BigUint, BigUint)> = vec![(two_b.clone(), &three_b - 1u32)];
+    let mut s = BigUint::zero();
+    let mut i = 1;
+    loop {
+        let step;
+        if i == 1 {
+            // 2a: smallest s >= n / 3B that conforms
+            step = Bleichenbacher98Step::SearchStart;
+            s = n.div_ceil(&three_b);
+            while !conforms(&s, &mut is_conforming) {
+                s += 1u32;
+            }
+        } else if intervals.len() > 1 {
+            // 2b: next s that conforms
+            step = Bleichenbacher98Step::SearchMultipleIntervals;
+            s += 1u32;
+            while !conforms(&s, &mut is_conforming) {
+                s += 1u32;
+            }
+        } else {
+            // 2c: search r and s together, which roughly halves the interval each time
+            step = Bleichenbacher98Step::SearchSingleInterval;
+            let (a, b_upper) = &intervals[0];
+            let mut r = (((b_upper * &s) - &two_b) * 2u32).div_ceil(n);
+            'search: loop {
+                let s_low = (&two_b + &r * n).div_ceil(b_upper);
+                let s_high = (&three_b + &r * n).div_ceil(a);
+                let mut candidate = s_low;
+                while candidate < s_high {
+                    if conforms(&candidate, &mut is_conforming) {
+                        s = candidate;
+                        break 'search;
+                    }
+                    candidate += 1u32;
+                }
+                r += 1u32;
+            }
+        }
+
+        // 3: narrow every interval to the values consistent with m * s being conforming
+        let mut narrowed: Vec<(BigUint, BigUint)> = vec![];
+        for (a, b_upper) in &intervals {
+            let r_low = (a * &s + 1u32 - &three_b).div_ceil(n);
+            let r_high = (b_upper * &s - &two_b) / n;
+            let mut r = r_low;
+            while r <= r_high {
+                let low = (&two_b + &r * n).div_ceil(&s).max(a.clone());
+                let high = ((&three_b - 1u32 + &r * n) / &s).min(b_upper.clone());
+                if low <= high {
+                    narrowed.push((low, high));
+                }
+                r += 1u32;
+            }
+        }
+        intervals = merge_intervals(narrowed);
+        progress(step, &s, &intervals);
+
+        // 4: done once a single value is left
+        if intervals.len() == 1 && intervals[0].0 == intervals[0].1 {
+            return biguint_to_bytes_padded(&intervals[0].0, len);
+        }
+        i += 1;
+    }
+}
@@ -0,0 +1,479 @@
+use crate::score_bytes;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+
+// ways of scoring candidate plaintexts. higher scores mean more plausible, and scores are only
+// meant to be compared between texts of about the same length
+pub trait Scorer {
+    fn score(&self, bytes: &[u8]) -> f64;
+}
+
+// the hard-coded English letter table of score_byte
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LetterTableScorer;
+
+impl Scorer for LetterTableScorer {
+    fn score(&self, bytes: &[u8]) -> f64 {
+        score_bytes(bytes) as f64
+    }
+}
+
+// relative frequencies of a-z in English text, in percent
+const ENGLISH_LETTER_FREQUENCIES: [f64; 26] = [
+    8.167, 1.492, 2.782, 4.253, 12.702, 2.228, 2.015, 6.094, 6.966, 0.153, 0.772, 4.025, 2.406,
+    6.749, 7.507, 1.929, 0.095, 5.987, 6.327, 9.056, 2.758, 0.978, 2.360, 0.150, 1.974, 0.074,
+];
+
+// the smallest probability any byte is given, so that unexpected bytes are heavily but not
+// infinitely penalised
+const MIN_PROBABILITY: f64 = 1e-5;
+
+// minus the chi-squared statistic of the byte counts against an expected distribution of bytes
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChiSquaredScorer {
+    expected: Vec<f64>,
+    fold_case: bool,
+}
+
+impl ChiSquaredScorer {
+    pub fn new(expected: &[f64], fold_case: bool) -> Self {
+        // expected holds a probability for each of the 256 byte values. with fold_case, A-Z are
+        // counted as a-z and their own probabilities are ignored
+        assert_eq!(expected.len(), 256, "Need a probability for every byte.");
+        ChiSquaredScorer {
+            expected: expected.iter().map(|p| p.max(MIN_PROBABILITY)).collect(),
+            fold_case,
+        }
+    }
+
+    pub fn english() -> Self {
+        // letters make up about 80% of English text and spaces most of the rest, with a little
+        // left over for digits, punctuation and newlines
+        let mut expected = vec![0.0; 256];
+        for (i, f) in ENGLISH_LETTER_FREQUENCIES.iter().enumerate() {
+            expected[b'a' as usize + i] = 0.8 * f / 100.0;
+        }
+        expected[b' ' as usize] = 0.17;
+        let others: Vec<u8> = (b'!'..=b'~')
+            .filter(|b| !b.is_ascii_alphabetic())
+            .chain([b'\n'])
+            .collect();
+        for &b in &others {
+            expected[b as usize] = 0.03 / others.len() as f64;
+        }
+        ChiSquaredScorer::new(&expected, true)
+    }
+
+    pub fn from_corpus(corpus: &[u8], fold_case: bool) -> Self {
+        // the byte frequencies of some representative text
+        let mut model = LanguageModel::new();
+        model.train(corpus);
+        ChiSquaredScorer::from_model(&model, fold_case)
+    }
+
+    pub fn from_model(model: &LanguageModel, fold_case: bool) -> Self {
+        let unigrams = model.counts(1);
+        let total = unigrams.values().sum::<u64>();
+        assert!(total > 0, "Model has no unigrams.");
+        let mut expected = vec![0.0; 256];
+        for (gram, &count) in unigrams {
+            expected[fold(gram[0], fold_case) as usize] += count as f64 / total as f64;
+        }
+        ChiSquaredScorer::new(&expected, fold_case)
+    }
+}
+
+fn fold(b: u8, fold_case: bool) -> u8 {
+    if fold_case { b.to_ascii_lowercase() } else { b }
+}
+
+impl Scorer for ChiSquaredScorer {
+    fn score(&self, bytes: &[u8]) -> f64 {
+        // every term would be 0 / 0
+        if bytes.is_empty() {
+            return 0.0;
+        }
+        let mut observed = [0usize; 256];
+        for &b in bytes {
+            observed[fold(b, self.fold_case) as usize] += 1;
+        }
+        let n = bytes.len() as f64;
+        let chi_squared: f64 = (0..256)
+            .filter(|&b| !(self.fold_case && (b as u8).is_ascii_uppercase()))
+            .map(|b| {
+                let expected = n * self.expected[b];
+                (observed[b] as f64 - expected).powi(2) / expected
+            })
+            .sum();
+        -chi_squared
+    }
+}
+
+// the log-likelihood of a text under the n-gram frequencies of a corpus, for n from 1 to 3
+#[derive(Clone, Debug, PartialEq)]
+pub struct NgramScorer {
+    n: usize,
+    log_probabilities: HashMap<Vec<u8>, f64>,
+    unseen: f64,
+}
+
+impl NgramScorer {
+    pub fn from_counts(n: usize, counts: &HashMap<Vec<u8>, u64>) -> Self {
+        // n-grams missing from counts are scored as if they had been seen a hundredth of a time
+        assert!((1..=3).contains(&n), "n must be 1, 2 or 3.");
+        assert!(
+            counts.keys().all(|gram| gram.len() == n),
+            "Every n-gram must have length n."
+        );
+        let total = counts.values().sum::<u64>().max(1) as f64;
+        NgramScorer {
+            n,
+            log_probabilities: counts
+                .iter()
+                .map(|(gram, &count)| (gram.clone(), (count as f64 / total).ln()))
+                .collect(),
+            unseen: (0.01 / total).ln(),
+        }
+    }
+
+    pub fn from_corpus(corpus: &[u8], n: usize) -> Self {
+        let mut model = LanguageModel::new();
+        model.train(corpus);
+        NgramScorer::from_model(&model, n)
+    }
+
+    pub fn from_model(model: &LanguageModel, n: usize) -> Self {
+        NgramScorer::from_counts(n, model.counts(n))
+    }
+}
+
+impl Scorer for NgramScorer {
+    fn score(&self, bytes: &[u8]) -> f64 {
+        // too short for a whole n-gram, which counts as one unseen one so that it never beats a
+        // text that has a real one
+        if bytes.len() < self.n {
+            return self.unseen;
+        }
+        bytes
+            .windows(self.n)
+            .map(|gram| *self.log_probabilities.get(gram).unwrap_or(&self.unseen))
+            .sum()
+    }
+}
+
+// the fraction of bytes that are printable ASCII or common whitespace, which says nothing about
+// the language but is robust for short or unusual plaintexts
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PrintableScorer;
+
+pub fn is_printable(b: u8) -> bool {
+    matches!(b, b' '..=b'~' | b'\t' | b'\n' | b'\r')
+}
+
+impl Scorer for PrintableScorer {
+    fn score(&self, bytes: &[u8]) -> f64 {
+        if bytes.is_empty() {
+            return 0.0;
+        }
+        bytes.iter().filter(|&&b| is_printable(b)).count() as f64 / bytes.len() as f64
+    }
+}
+
+// unigram, bigram and trigram counts of a corpus, which can be saved in a compact binary form
+// and loaded again to build scorers for languages and formats other than English
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LanguageModel {
+    counts: [HashMap<Vec<u8>, u64>; 3],
+}
+
+const LANGUAGE_MODEL_MAGIC: &[u8] = b"NGRAM1";
+
+impl LanguageModel {
+    pub fn new() -> Self {
+        LanguageModel::default()
+    }
+
+    pub fn counts(&self, n: usize) -> &HashMap<Vec<u8>, u64> {
+        assert!((1..=3).contains(&n), "n must be 1, 2 or 3.");
+        &self.counts[n - 1]
+    }
+
+    fn add_windows(&mut self, text: &[u8], carried: usize) {
+        // text starts with the last carried bytes seen before, which only count towards the
+        // n-grams that straddle the join
+        for n in 1..=3 {
+            let start = carried.saturating_sub(n - 1);
+            for gram in text[start..].windows(n) {
+                *self.counts[n - 1].entry(gram.to_vec()).or_default() += 1;
+            }
+        }
+    }
+
+    pub fn train(&mut self, text: &[u8]) {
+        // texts are counted separately, so no n-gram spans two of them
+        self.add_windows(text, 0);
+    }
+
+    pub fn train_from_reader<R: Read>(mut reader: R) -> io::Result<Self> {
+        // the whole of the stream as one text, read a chunk at a time
+        let mut model = LanguageModel::new();
+        let mut buffer = vec![0u8; 1 << 16];
+        let mut carried: Vec<u8> = vec![];
+        loop {
+            let read = match reader.read(&mut buffer) {
+                Ok(0) => return Ok(model),
+                Ok(read) => read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            let text = [carried.as_slice(), &buffer[..read]].concat();
+            model.add_windows(&text, carried.len());
+            carried = text[text.len().saturating_sub(2)..].to_vec();
+        }
+    }
+
+    pub fn train_from_file(filepath: &str) -> io::Result<Self> {
+        LanguageModel::train_from_reader(BufReader::new(File::open(filepath)?))
+    }
+
+    pub fn pruned(&self, min_count: u64) -> Self {
+        // drops the rare n-grams, which are most of a large model and matter least for scoring
+        LanguageModel {
+            counts: self.counts.clone().map(|counts| {
+                counts
+                    .into_iter()
+                    .filter(|&(_, count)| count >= min_count)
+                    .collect()
+            }),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        // the magic, then for each n the number of n-grams as a u32 followed by each n-gram in
+        // sorted order and its count as a LEB128 varint
+        let mut out = LANGUAGE_MODEL_MAGIC.to_vec();
+        for counts in &self.counts {
+            let mut grams: Vec<(&Vec<u8>, &u64)> = counts.iter().collect();
+            grams.sort();
+            out.extend((grams.len() as u32).to_be_bytes());
+            for (gram, &count) in grams {
+                out.extend(gram);
+                let mut count = count;
+                while count >= 0x80 {
+                    out.push((count & 0x7f) as u8 | 0x80);
+                    count >>= 7;
+                }
+                out.push(count as u8);
+            }
+        }
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        // None if bytes is not a model written by to_bytes
+        let mut rest = bytes.strip_prefix(LANGUAGE_MODEL_MAGIC)?;
+        let mut model = LanguageModel::new();
+        for n in 1..=3 {
+            let (len, tail) = rest.split_first_chunk::<4>()?;
+            rest = tail;
+            for _ in 0..u32::from_be_bytes(*len) {
+                let (gram, tail) = rest.split_at_checked(n)?;
+                let mut count = 0u64;
+                let mut shift = 0;
+                let mut tail = tail.iter();
+                loop {
+                    let &b = tail.next()?;
+                    if shift > 63 {
+                        return None;
+                    }
+                    count |= ((b & 0x7f) as u64) << shift;
+                    shift += 7;
+                    if b < 0x80 {
+                        break;
+                    }
+                }
+                rest = tail.as_slice();
+                model.counts[n - 1].insert(gram.to_vec(), count);
+            }
+        }
+        rest.is_empty().then_some(model)
+    }
+
+    pub fn save(&self, filepath: &str) -> io::Result<()> {
+        std::fs::write(filepath, self.to_bytes())
+    }
+
+    pub fn load(filepath: &str) -> io::Result<Self> {
+        LanguageModel::from_bytes(&std::fs::read(filepath)?)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Not a language model."))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileType {
+    Pe,
+    Elf,
+    Zip,
+    Png,
+    Gzip,
+    Pdf,
+}
+
+const FILE_TYPES: [FileType; 6] = [
+    FileType::Pe,
+    FileType::Elf,
+    FileType::Zip,
+    FileType::Png,
+    FileType::Gzip,
+    FileType::Pdf,
+];
+
+fn u16_le(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(*bytes.get(at..)?.first_chunk()?))
+}
+
+fn u32_le(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(*bytes.get(at..)?.first_chunk()?))
+}
+
+fn u32_be(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(*bytes.get(at..)?.first_chunk()?))
+}
+
+fn pe_structure(bytes: &[u8]) -> u32 {
+    // the MZ stub, then the offset at 0x3c of the PE signature
+    if !bytes.starts_with(b"MZ") {
+        return 0;
+    }
+    let signature = u32_le(bytes, 0x3c).and_then(|at| bytes.get(at as usize..)?.get(..4));
+    1 + 2 * (signature == Some(b"PE\0\0")) as u32
+}
+
+fn elf_structure(bytes: &[u8]) -> u32 {
+    // the magic, then a 32 or 64-bit class, a byte order and version 1
+    if !bytes.starts_with(b"\x7fELF") {
+        return 0;
+    }
+    let class = matches!(bytes.get(4), Some(1 | 2));
+    let byte_order = matches!(bytes.get(5), Some(1 | 2));
+    let version = bytes.get(6) == Some(&1);
+    1 + class as u32 + byte_order as u32 + version as u32
+}
+
+fn zip_structure(bytes: &[u8]) -> u32 {
+    // a point for each local file header with a sensible version and compression method, and
+    // for the end of central directory record
+    let local_headers = bytes
+        .windows(4)
+        .enumerate()
+        .filter(|&(_, w)| w == b"PK\x03\x04")
+        .filter(|&(at, _)| {
+            u16_le(bytes, at + 4).is_some_and(|version| version <= 63)
+                && u16_le(bytes, at + 8).is_some_and(|method| matches!(method, 0 | 8 | 9 | 12 | 14))
+        })
+        .count();
+    let end = bytes.windows(4).any(|w| w == b"PK\x05\x06");
+    local_headers as u32 + end as u32
+}
+
+fn png_structure(bytes: &[u8]) -> u32 {
+    // the signature, then a point for each whole chunk with a four letter type
+    if !bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return 0;
+    }
+    let mut points = 1;
+    let mut at = 8;
+    while let Some(len) = u32_be(bytes, at) {
+        let chunk_type = bytes.get(at + 4..at + 8);
+        let whole = at + 12 + len as usize <= bytes.len();
+        if !whole || !chunk_type.is_some_and(|t| t.iter().all(u8::is_ascii_alphabetic)) {
+            break;
+        }
+        points += 1;
+        at += 12 + len as usize;
+    }
+    points
+}
+
+fn gzip_structure(bytes: &[u8]) -> u32 {
+    // the magic, then deflate as the method and no reserved flags
+    if !bytes.starts_with(b"\x1f\x8b") {
+        return 0;
+    }
+    let deflate = bytes.get(2) == Some(&8);
+    let flags = bytes.get(3).is_some_and(|flags| flags & 0xe0 == 0);
+    1 + deflate as u32 + flags as u32
+}
+
+fn pdf_structure(bytes: &[u8]) -> u32 {
+    // the header with a version, and the end of file marker
+    if !bytes.starts_with(b"%PDF-") {
+        return 0;
+    }
+    let version = matches!(bytes.get(5..7), Some(b"1." | b"2."));
+    let end = bytes.windows(5).any(|w| w == b"%%EOF");
+    1 + version as u32 + end as u32
+}
+
+fn structure(bytes: &[u8], file_type: FileType) -> u32 {
+    match file_type {
+        FileType::Pe => pe_structure(bytes),
+        FileType::Elf => elf_structure(bytes),
+        FileType::Zip => zip_structure(bytes),
+        FileType::Png => png_structure(bytes),
+        FileType::Gzip => gzip_structure(bytes),
+        FileType::Pdf => pdf_structure(bytes),
+    }
+}
+
+pub fn detect_file_type(bytes: &[u8]) -> Option<FileType> {
+    // the format whose magic and structure bytes match best, if any match at all
+    FILE_TYPES
+        .iter()
+        .map(|&file_type| (structure(bytes, file_type), file_type))
+        .filter(|&(points, _)| points > 0)
+        .max_by_key(|&(points, _)| points)
+        .map(|(_, file_type)| file_type)
+}
+
+pub fn shannon_entropy(bytes: &[u8]) -> f64 {
+    // in bits per byte, from 0 for a single repeated byte to 8 for uniformly random bytes
+    let mut counts = [0usize; 256];
+    for &b in bytes {
+        counts[b as usize] += 1;
+    }
+    let n = bytes.len() as f64;
+    counts
+        .iter()
+        .filter(|&&c| c > 0)
+        .map(|&c| {
+            let p = c as f64 / n;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+// scores plaintexts as binary files rather than text. each magic number and piece of valid
+// structure of a known format is worth a point, and on top of that up to one more for looking
+// like a typical binary: zero padding is the commonest byte in executables and most other
+// formats, and the wrong key mixes the byte counts of a file towards random. xoring with a
+// single byte only permutes the byte counts, so for those the zeros alone decide
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FileTypeScorer;
+
+impl Scorer for FileTypeScorer {
+    fn score(&self, bytes: &[u8]) -> f64 {
+        if bytes.is_empty() {
+            return 0.0;
+        }
+        let structure = FILE_TYPES
+            .iter()
+            .map(|&file_type| structure(bytes, file_type))
+            .max()
+            .unwrap();
+        let zeros = bytes.iter().filter(|&&b| b == 0).count() as f64 / bytes.len() as f64;
+        let profile = (zeros + 1.0 - shannon_entropy(bytes) / 8.0) / 2.0;
+        structure as f64 + profile
+    }
+}
@@ -1,6 +1,31 @@
+use cryptopals::bignum::*;
+use cryptopals::bit_matrix::*;
+use cryptopals::cbc_mac::*;
+use cryptopals::collision::*;
+use cryptopals::compression_oracle::*;
+use cryptopals::dh::*;
+use cryptopals::dsa::*;
+use cryptopals::ec::*;
+use cryptopals::gcm::*;
+use cryptopals::gf128::*;
+use cryptopals::kangaroo::*;
+use cryptopals::lll::*;
+use cryptopals::many_time_pad::*;
+use cryptopals::md_hash::*;
+use cryptopals::rsa::*;
+use cryptopals::scoring::*;
+use cryptopals::xor::*;
+use cryptopals::xor_variants::*;
 use cryptopals::*;
+use num_bigint::{BigInt, BigUint};
+use num_rational::BigRational;
+use num_traits::{One, Zero};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use sha1::{Digest, Sha1};
 
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests {
 
     use super::*;
@@ -137,7 +162,10 @@ mod tests {
     fn t_break_repeating_key_xor() {
         let input_bytes: Vec<u8> = b64_file_to_bytes("./challenge_files/6.txt");
         let keysizes = guess_xor_keysize(&input_bytes);
-        assert_eq!(keysizes, vec![2, 3, 5, 13, 18, 29, 58, 4, 6, 7]);
+        assert_eq!(keysizes, vec![29, 58, 18, 36, 25, 12, 3, 10, 27, 6]);
+        // too short for four blocks of most keysizes, which used to panic
+        assert_eq!(guess_xor_keysize(b"short"), vec![1]);
+        assert!(guess_xor_keysize(b"").is_empty());
         let key_plaintext_score = break_repeating_key_xor(&input_bytes, keysizes);
         let key_str = String::from_utf8(key_plaintext_score.0).unwrap();
         let plaintext_str = String::from_utf8(key_plaintext_score.1).unwrap();
@@ -203,7 +231,7 @@ mod tests {
     fn t_decrypt_ecb() {
         let input_bytes: Vec<u8> = b64_file_to_bytes("./challenge_files/7.txt");
         let key: &str = "YELLOW SUBMARINE";
-        let key_bytes = string_to_bytes(&key);
+        let key_bytes = string_to_bytes(key);
         let decrypted = decrypt_aes_128_ecb(&input_bytes, &key_bytes);
         let decrypted_str = String::from_utf8(decrypted).unwrap();
         assert_eq!(
@@ -217,7 +245,7 @@ mod tests {
         let input: &str = "foobarbazquxfoo!";
         let input_bytes: Vec<u8> = string_to_bytes(input);
         let key: &str = "YELLOW SUBMARINE";
-        let key_bytes = string_to_bytes(&key);
+        let key_bytes = string_to_bytes(key);
         let encrypted = encrypt_aes_128_ecb(&input_bytes, &key_bytes);
         assert_eq!(
             encrypted,
@@ -232,7 +260,7 @@ mod tests {
         let input: &str = "foobarbazquxfoo!foobarbazquxfoo!";
         let input_bytes: Vec<u8> = string_to_bytes(input);
         let key: &str = "YELLOW SUBMARINE";
-        let key_bytes = string_to_bytes(&key);
+        let key_bytes = string_to_bytes(key);
         let encrypted = encrypt_aes_128_ecb(&input_bytes, &key_bytes);
         assert_eq!(
             encrypted,
@@ -248,7 +276,7 @@ mod tests {
         let input: &str = "foobarbazquxfoo!foobarbazquxfoo!";
         let input_bytes: Vec<u8> = string_to_bytes(input);
         let key: &str = "YELLOW SUBMARINE";
-        let key_bytes = string_to_bytes(&key);
+        let key_bytes = string_to_bytes(key);
         let encrypted = encrypt_aes_128_ecb(&input_bytes, &key_bytes);
         let decrypted = decrypt_aes_128_ecb(&encrypted, &key_bytes);
         let decrypted_string = String::from_utf8(decrypted).unwrap();
@@ -327,7 +355,7 @@ mod tests {
     fn t_decrypt_aes_128_cbc() {
         let input_bytes = b64_file_to_bytes("./challenge_files/10.txt");
         let key: &str = "YELLOW SUBMARINE";
-        let key_bytes = string_to_bytes(&key);
+        let key_bytes = string_to_bytes(key);
         let iv_bytes = vec![0; 16];
         let decrypted = decrypt_aes_128_cbc(&input_bytes, &key_bytes, &iv_bytes);
         let decrypted_str = String::from_utf8(decrypted).unwrap();
@@ -342,7 +370,7 @@ mod tests {
         let input = "I'm back and I'm ringin' the bell \nA rockin' on the mike while the fly girls yell \nIn ecstasy in the back of me \nWell that's my DJ Deshay cuttin' all them Z's \nHittin' hard and the girlies goin' crazy \nVanilla's on the mike, man I'm not lazy. \n\nI'm lettin' my drug kick in \nIt controls my mouth and I begin \nTo just let it flow, let my concepts go \nMy posse's to the side yellin', Go Vanilla Go! \n\nSmooth 'cause that's the way I will be \nAnd if you don't give a damn, then \nWhy you starin' at me \nSo get off 'cause I control the stage \nThere's no dissin' allowed \nI'm in my own phase \nThe girlies sa y they love me and that is ok \nAnd I can dance better than any kid n' play \n\nStage 2 -- Yea the one ya' wanna listen to \nIt's off my head so let the beat play through \nSo I can funk it up and make it sound good \n1-2-3 Yo -- Knock on some wood \nFor good luck, I like my rhymes atrocious \nSupercalafragilisticexpialidocious \nI'm an effect and that you can bet \nI can take a fly girl and make her wet. \n\nI'm like Samson -- Samson to Delilah \nThere's no denyin', You can try to hang \nBut you'll keep tryin' to get my style \nOver and over, practice makes perfect \nBut not if you're a loafer. \n\nYou'll get nowhere, no place, no time, no girls \nSoon -- Oh my God, homebody, you probably eat \nSpaghetti with a spoon! Come on and say it! \n\nVIP. Vanilla Ice yep, yep, I'm comin' hard like a rhino \nIntoxicating so you stagger like a wino \nSo punks stop trying and girl stop cryin' \nVanilla Ice is sellin' and you people are buyin' \n'Cause why the freaks are jockin' like Crazy Glue \nMovin' and groovin' trying to sing along \nAll through the ghetto groovin' this here song \nNow you're amazed by the VIP posse. \n\nSteppin' so hard like a German Nazi \nStartled by the bases hittin' ground \nThere's no trippin' on mine, I'm just gettin' down \nSparkamatic, I'm hangin' tight like a fanatic \nYou trapped me once and I thought that \nYou might have it \nSo step down and lend me your ear \n'89 in my time! You, '90 is my year. \n\nYou're weakenin' fast, YO! and I can tell it \nYour body's gettin' hot, so, so I can smell it \nSo don't be mad and don't be sad \n'Cause the lyrics belong to ICE, You can call me Dad \nYou're pitchin' a fit, so step back and endure \nLet the witch doctor, Ice, do the dance to cure \nSo come up close and don't be square \nYou wanna battle me -- Anytime, anywhere \n\nYou thought that I was weak, Boy, you're dead wrong \nSo come on, everybody and sing this song \n\nSay -- Play that funky music Say, go white boy, go white boy go \nplay that funky music Go white boy, go white boy, go \nLay down and boogie and play that funky music till you die. \n\nPlay that funky music Come on, Come on, let me hear \nPlay that funky music white boy you say it, say it \nPlay that funky music A little louder now \nPlay that funky music, white boy Come on, Come on, Come on \nPlay that funky music \n\u{4}\u{4}\u{4}\u{4}";
         let input_bytes = string_to_bytes(input);
         let key: &str = "YELLOW SUBMARINE";
-        let key_bytes = string_to_bytes(&key);
+        let key_bytes = string_to_bytes(key);
         let iv_bytes = vec![0; 16];
         let encrypted = encrypt_aes_128_cbc(&input_bytes, &key_bytes, &iv_bytes);
         let original_file = b64_file_to_bytes("./challenge_files/10.txt");
@@ -369,4 +397,2240 @@ mod tests {
         let result: String = detect_ecb_cbc();
         assert!(result.contains("ecb") || result.contains("cbc"));
     }
+
+    #[test]
+    fn t_is_probable_prime() {
+        let mut rng = StdRng::seed_from_u64(39);
+        let primes: Vec<u32> = vec![2, 3, 97, 7919, 104729, 2147483647];
+        let composites: Vec<u32> = vec![0, 1, 4, 561, 1105, 7917, 104730];
+        for p in primes {
+            assert!(is_probable_prime(&BigUint::from(p), 20, &mut rng));
+        }
+        for c in composites {
+            assert!(!is_probable_prime(&BigUint::from(c), 20, &mut rng));
+        }
+    }
+
+    #[test]
+    fn t_generate_prime() {
+        let mut rng = StdRng::seed_from_u64(39);
+        let p = generate_prime(128, &mut rng);
+        assert_eq!(p.bits(), 128);
+        assert!(is_probable_prime(&p, 40, &mut rng));
+    }
+
+    #[test]
+    fn t_crt() {
+        let residues: Vec<BigUint> = vec![2u32, 3, 2].into_iter().map(BigUint::from).collect();
+        let moduli: Vec<BigUint> = vec![3u32, 5, 7].into_iter().map(BigUint::from).collect();
+        let (x, n) = crt(&residues, &moduli);
+        assert_eq!(x, BigUint::from(23u32));
+        assert_eq!(n, BigUint::from(105u32));
+    }
+
+    // Challenge 39
+    #[test]
+    fn t_rsa_textbook_example() {
+        let public_key = RsaPublicKey {
+            e: BigUint::from(17u32),
+            n: BigUint::from(3233u32),
+        };
+        let private_key = RsaPrivateKey {
+            d: BigUint::from(2753u32),
+            n: BigUint::from(3233u32),
+        };
+        let c = rsa_encrypt_int(&BigUint::from(65u32), &public_key);
+        assert_eq!(c, BigUint::from(2790u32));
+        assert_eq!(rsa_decrypt_int(&c, &private_key), BigUint::from(65u32));
+    }
+
+    #[test]
+    fn t_rsa_roundtrip() {
+        let mut rng = StdRng::seed_from_u64(39);
+        let (public_key, private_key) = generate_rsa_keypair(512, &BigUint::from(3u32), &mut rng);
+        assert_eq!(public_key.n.bits(), 512);
+        let input_bytes = string_to_bytes("Cooking MC's like a pound of bacon");
+        let encrypted = rsa_encrypt(&input_bytes, &public_key);
+        assert_ne!(encrypted, input_bytes);
+        let decrypted = rsa_decrypt(&encrypted, &private_key);
+        assert_eq!(decrypted, input_bytes);
+    }
+
+    // Challenge 40
+    #[test]
+    fn t_rsa_broadcast_attack() {
+        let mut rng = StdRng::seed_from_u64(40);
+        let input_bytes = string_to_bytes("Now that the party is jumping");
+        let ciphertexts_keys: Vec<(Vec<u8>, RsaPublicKey)> = (0..3)
+            .map(|_| {
+                let (public_key, _) = generate_rsa_keypair(512, &BigUint::from(3u32), &mut rng);
+                (rsa_encrypt(&input_bytes, &public_key), public_key)
+            })
+            .collect();
+        let recovered = rsa_broadcast_attack(&ciphertexts_keys);
+        assert_eq!(recovered, input_bytes);
+    }
+
+    #[test]
+    #[should_panic(expected = "Need at least e ciphertexts.")]
+    fn t_rsa_broadcast_attack_needs_ciphertexts() {
+        rsa_broadcast_attack(&[]);
+    }
+
+    // Challenge 41
+    #[test]
+    fn t_decrypt_once_oracle() {
+        let mut rng = StdRng::seed_from_u64(41);
+        let (public_key, private_key) =
+            generate_rsa_keypair(512, &BigUint::from(65537u32), &mut rng);
+        let mut oracle = DecryptOnceOracle::new(private_key);
+        let encrypted = rsa_encrypt(
+            &string_to_bytes("{time: 1356304276, social: '555-55-5555'}"),
+            &public_key,
+        );
+        assert!(oracle.decrypt(&encrypted).is_some());
+        assert!(oracle.decrypt(&encrypted).is_none());
+        // adding n gives the same ciphertext mod n, which the oracle still recognises
+        let wrapped = BigUint::from_bytes_be(&encrypted) + &public_key.n;
+        assert!(oracle.decrypt(&wrapped.to_bytes_be()).is_none());
+    }
+
+    #[test]
+    fn t_recover_unpadded_message() {
+        let mut rng = StdRng::seed_from_u64(41);
+        let (public_key, private_key) =
+            generate_rsa_keypair(512, &BigUint::from(65537u32), &mut rng);
+        let mut oracle = DecryptOnceOracle::new(private_key);
+        let input_bytes = string_to_bytes("{time: 1356304276, social: '555-55-5555'}");
+        let encrypted = rsa_encrypt(&input_bytes, &public_key);
+        // the victim's own request uses up the one decryption of this ciphertext
+        assert!(oracle.decrypt(&encrypted).is_some());
+        let recovered = recover_unpadded_message(&encrypted, &public_key, &mut oracle, &mut rng);
+        assert_eq!(recovered, input_bytes);
+    }
+
+    // Challenge 42
+    #[test]
+    fn t_pkcs1_v15_encode_signature() {
+        let encoded = pkcs1_v15_encode_signature(b"hi mom", HashAlgorithm::Sha1, 128).unwrap();
+        assert_eq!(encoded.len(), 128);
+        assert_eq!(encoded[..2], [0x00, 0x01]);
+        assert!(encoded[2..92].iter().all(|&b| b == 0xff));
+        assert_eq!(encoded[92], 0x00);
+        assert_eq!(encoded[93..108], *HashAlgorithm::Sha1.digest_info_prefix());
+        assert_eq!(
+            bytes_to_hex(encoded[108..].to_vec()),
+            "925a89b43f3caff507db0a86d20a2428007f10b6"
+        );
+        // 35 bytes of DigestInfo, 3 of framing and at least 8 of padding
+        assert!(pkcs1_v15_encode_signature(b"hi mom", HashAlgorithm::Sha1, 46).is_some());
+        assert_eq!(
+            pkcs1_v15_encode_signature(b"hi mom", HashAlgorithm::Sha1, 45),
+            None
+        );
+    }
+
+    #[test]
+    fn t_rsa_sign_verify_pkcs1_v15() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let (public_key, private_key) = generate_rsa_keypair(1024, &BigUint::from(3u32), &mut rng);
+        for hash in [HashAlgorithm::Sha1, HashAlgorithm::Sha256] {
+            let signature = rsa_sign_pkcs1_v15(b"hi mom", hash, &private_key).unwrap();
+            assert_eq!(signature.len(), 128);
+            assert!(rsa_verify_pkcs1_v15(
+                b"hi mom",
+                &signature,
+                hash,
+                &public_key
+            ));
+            assert!(rsa_verify_pkcs1_v15_sloppy(
+                b"hi mom",
+                &signature,
+                hash,
+                &public_key
+            ));
+            assert!(!rsa_verify_pkcs1_v15(
+                b"hi dad",
+                &signature,
+                hash,
+                &public_key
+            ));
+            assert!(!rsa_verify_pkcs1_v15_sloppy(
+                b"hi dad",
+                &signature,
+                hash,
+                &public_key
+            ));
+        }
+        // 48 bytes of modulus cannot hold a SHA-256 block, so nothing signs or verifies under it
+        let (public_key, private_key) = generate_rsa_keypair(384, &BigUint::from(3u32), &mut rng);
+        assert_eq!(
+            rsa_sign_pkcs1_v15(b"hi mom", HashAlgorithm::Sha256, &private_key),
+            None
+        );
+        let signature = rsa_sign_pkcs1_v15(b"hi mom", HashAlgorithm::Sha1, &private_key).unwrap();
+        assert!(!rsa_verify_pkcs1_v15(
+            b"hi mom",
+            &signature,
+            HashAlgorithm::Sha256,
+            &public_key
+        ));
+    }
+
+    #[test]
+    fn t_forge_pkcs1_v15_signature_e3() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let (public_key, _) = generate_rsa_keypair(1024, &BigUint::from(3u32), &mut rng);
+        let forged =
+            forge_pkcs1_v15_signature_e3(b"hi mom", HashAlgorithm::Sha1, &public_key).unwrap();
+        assert!(rsa_verify_pkcs1_v15_sloppy(
+            b"hi mom",
+            &forged,
+            HashAlgorithm::Sha1,
+            &public_key
+        ));
+        assert!(!rsa_verify_pkcs1_v15(
+            b"hi mom",
+            &forged,
+            HashAlgorithm::Sha1,
+            &public_key
+        ));
+        // a SHA-256 DigestInfo leaves too little room for garbage under a 1024-bit modulus
+        assert!(
+            forge_pkcs1_v15_signature_e3(b"hi mom", HashAlgorithm::Sha256, &public_key).is_none()
+        );
+    }
+
+    #[test]
+    fn t_forge_pkcs1_v15_signature_e3_sha256() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let (public_key, _) = generate_rsa_keypair(2048, &BigUint::from(3u32), &mut rng);
+        let forged =
+            forge_pkcs1_v15_signature_e3(b"hi mom", HashAlgorithm::Sha256, &public_key).unwrap();
+        assert!(rsa_verify_pkcs1_v15_sloppy(
+            b"hi mom",
+            &forged,
+            HashAlgorithm::Sha256,
+            &public_key
+        ));
+        assert!(!rsa_verify_pkcs1_v15(
+            b"hi mom",
+            &forged,
+            HashAlgorithm::Sha256,
+            &public_key
+        ));
+    }
+
+    // Challenge 43
+    #[test]
+    fn t_dsa_sign_verify() {
+        let mut rng = StdRng::seed_from_u64(43);
+        let params = DsaParams::challenge();
+        let (public_key, private_key) = generate_dsa_keypair(&params, &mut rng);
+        let signature = dsa_sign(b"hi mom", &private_key, &mut rng);
+        assert!(dsa_verify(b"hi mom", &signature, &public_key));
+        assert!(!dsa_verify(b"hi dad", &signature, &public_key));
+    }
+
+    #[test]
+    fn t_dsa_challenge_signature() {
+        let public_key = DsaPublicKey {
+            params: DsaParams::challenge(),
+            y: hex_to_biguint(
+                "84ad4719d044495496a3201c8ff484feb45b962e7302e56a392aee4abab3e4bdebf2955b4736012f21a08084056b19bcd7fee56048e004e44984e2f411788efdc837a0d2e5abb7b555039fd243ac01f0fb2ed1dec568280ce678e931868d23eb095fde9d3779191b8c0299d6e07bbb283e6633451e535c45513b2d33c99ea17",
+            ),
+        };
+        let message = b"For those that envy a MC it can be hazardous to your health\nSo be friendly, a matter of life and death, just like a etch-a-sketch\n";
+        assert_eq!(
+            dsa_hash(message),
+            hex_to_biguint("d2d0714f014a9784047eaeccf956520045c45265")
+        );
+        let signature = DsaSignature {
+            r: BigUint::parse_bytes(b"548099063082341131477253921760299949438196259240", 10)
+                .unwrap(),
+            s: BigUint::parse_bytes(b"857042759984254168557880549501802188789837994940", 10)
+                .unwrap(),
+        };
+        assert!(dsa_verify(message, &signature, &public_key));
+        let x = dsa_brute_force_nonce(message, &signature, &public_key, 1 << 16).unwrap();
+        let x_hex = x.to_str_radix(16);
+        assert_eq!(
+            bytes_to_hex(Sha1::digest(x_hex.as_bytes()).to_vec()),
+            "0954edd5e0afe5542a4adf012611a91912a3ec16"
+        );
+    }
+
+    #[test]
+    fn t_dsa_x_from_nonce() {
+        let mut rng = StdRng::seed_from_u64(43);
+        let params = DsaParams::challenge();
+        let (_, private_key) = generate_dsa_keypair(&params, &mut rng);
+        let k = BigUint::from(31337u32);
+        let signature = dsa_sign_with_nonce(b"hi mom", &private_key, &k).unwrap();
+        let x = dsa_x_from_nonce(&dsa_hash(b"hi mom"), &signature, &k, &params.q).unwrap();
+        assert_eq!(x, private_key.x);
+    }
+
+    // Challenge 44
+    #[test]
+    fn t_find_repeated_nonce() {
+        let r = |v: u32| DsaSignature {
+            r: BigUint::from(v),
+            s: BigUint::one(),
+        };
+        assert_eq!(find_repeated_nonce(&[r(1), r(2), r(3)]), None);
+        assert_eq!(find_repeated_nonce(&[r(1), r(2), r(3), r(2)]), Some((1, 3)));
+    }
+
+    #[test]
+    fn t_dsa_recover_x_from_repeated_nonce() {
+        let mut rng = StdRng::seed_from_u64(44);
+        let params = DsaParams::challenge();
+        let (public_key, private_key) = generate_dsa_keypair(&params, &mut rng);
+        let k = random_biguint_range(&BigUint::one(), &params.q, &mut rng);
+        let messages: Vec<&[u8]> = vec![
+            b"Listen for me, you better listen for me now. ",
+            b"Pure black people mon is all I mon know. ",
+            b"Yeah me shoes a an tear up an' now me toes a show ",
+            b"Where me a born in are de one Toronto, so ",
+        ];
+        let messages_signatures: Vec<(Vec<u8>, DsaSignature)> = messages
+            .iter()
+            .enumerate()
+            .map(|(i, m)| {
+                let signature = if i == 1 || i == 3 {
+                    dsa_sign_with_nonce(m, &private_key, &k).unwrap()
+                } else {
+                    dsa_sign(m, &private_key, &mut rng)
+                };
+                (m.to_vec(), signature)
+            })
+            .collect();
+        let x = dsa_recover_x_from_repeated_nonce(&messages_signatures, &public_key).unwrap();
+        assert_eq!(x, private_key.x);
+        assert_eq!(
+            dsa_recover_x_from_repeated_nonce(&messages_signatures[..3], &public_key),
+            None
+        );
+    }
+
+    // Challenge 45
+    #[test]
+    fn t_dsa_magic_signature_g_zero() {
+        let mut rng = StdRng::seed_from_u64(45);
+        let params = DsaParams {
+            g: BigUint::zero(),
+            ..DsaParams::challenge()
+        };
+        let (public_key, private_key) = generate_dsa_keypair(&params, &mut rng);
+        let signed =
+            dsa_sign_with_nonce(b"Hello, world", &private_key, &BigUint::from(7u32)).unwrap();
+        assert_eq!(signed.r, BigUint::zero());
+        let signature = dsa_magic_signature_g_zero();
+        for message in [&b"Hello, world"[..], &b"Goodbye, world"[..]] {
+            assert!(dsa_verify_sloppy(message, &signature, &public_key));
+            assert!(!dsa_verify(message, &signature, &public_key));
+        }
+    }
+
+    #[test]
+    fn t_dsa_magic_signature_g_p_plus_one() {
+        let mut rng = StdRng::seed_from_u64(45);
+        let (mut public_key, _) = generate_dsa_keypair(&DsaParams::challenge(), &mut rng);
+        public_key.params.g = &public_key.params.p + 1u32;
+        let signature = dsa_magic_signature_g_p_plus_one(&public_key, &BigUint::from(1337u32));
+        assert!(dsa_verify(b"Hello, world", &signature, &public_key));
+        assert!(dsa_verify(b"Goodbye, world", &signature, &public_key));
+    }
+
+    // Challenge 46
+    #[test]
+    fn t_rsa_parity_oracle_attack() {
+        let mut rng = StdRng::seed_from_u64(46);
+        let (public_key, private_key) =
+            generate_rsa_keypair(1024, &BigUint::from(65537u32), &mut rng);
+        let oracle = RsaParityOracle::new(private_key);
+        let input_bytes = b64_to_bytes(
+            "VGhhdCdzIHdoeSBJIGZvdW5kIHlvdSBkb24ndCBwbGF5IGFyb3VuZCB3aXRoIHRoZSBGdW5reSBDb2xkIE1lZGluYQ==",
+        );
+        let encrypted = BigUint::from_bytes_be(&rsa_encrypt(&input_bytes, &public_key));
+        let mut upper_bounds: Vec<BigUint> = vec![];
+        let recovered = rsa_parity_oracle_attack(
+            &encrypted,
+            &public_key,
+            |c| oracle.is_even(c),
+            |upper| upper_bounds.push(upper.clone()),
+        );
+        assert_eq!(recovered.to_bytes_be(), input_bytes);
+        assert_eq!(upper_bounds.len(), 1024);
+        assert!(upper_bounds.windows(2).all(|w| w[1] <= w[0]));
+    }
+
+    #[test]
+    fn t_pkcs1_v15_encryption_roundtrip() {
+        let mut rng = StdRng::seed_from_u64(47);
+        let (public_key, private_key) = generate_rsa_keypair(512, &BigUint::from(3u32), &mut rng);
+        let encrypted = rsa_encrypt_pkcs1_v15(b"kick it, CC", &public_key, &mut rng);
+        assert_eq!(encrypted.len(), 64);
+        let decrypted = rsa_decrypt_pkcs1_v15(&encrypted, &private_key).unwrap();
+        assert_eq!(decrypted, b"kick it, CC");
+        assert_eq!(
+            pkcs1_v15_unpad_encryption(&[0x00, 0x02, 0x01, 0x00, 0x61]),
+            None
+        );
+    }
+
+    // Challenge 47
+    #[test]
+    fn t_bleichenbacher_98_attack_256() {
+        let mut rng = StdRng::seed_from_u64(47);
+        let (public_key, private_key) = generate_rsa_keypair(256, &BigUint::from(3u32), &mut rng);
+        let oracle = Pkcs1ConformanceOracle::new(private_key);
+        let encrypted = rsa_encrypt_pkcs1_v15(b"kick it, CC", &public_key, &mut rng);
+        let c = BigUint::from_bytes_be(&encrypted);
+        assert!(oracle.is_conforming(&c));
+        let mut steps: Vec<Bleichenbacher98Step> = vec![];
+        let recovered = bleichenbacher_98_attack(
+            &c,
+            &public_key,
+            |c| oracle.is_conforming(c),
+            |step, _, _| steps.push(step),
+        );
+        assert_eq!(
+            pkcs1_v15_unpad_encryption(&recovered).unwrap(),
+            b"kick it, CC"
+        );
+        assert_eq!(steps[0], Bleichenbacher98Step::SearchStart);
+    }
+
+    // Challenge 48
+    #[test]
+    fn t_bleichenbacher_98_attack_768() {
+        let mut rng = StdRng::seed_from_u64(48);
+        let (public_key, private_key) = generate_rsa_keypair(768, &BigUint::from(3u32), &mut rng);
+        let oracle = Pkcs1ConformanceOracle::new(private_key);
+        let encrypted = rsa_encrypt_pkcs1_v15(b"kick it, CC", &public_key, &mut rng);
+        let recovered = bleichenbacher_98_attack(
+            &BigUint::from_bytes_be(&encrypted),
+            &public_key,
+            |c| oracle.is_conforming(c),
+            |_, _, _| {},
+        );
+        assert_eq!(
+            pkcs1_v15_unpad_encryption(&recovered).unwrap(),
+            b"kick it, CC"
+        );
+    }
+
+    // Challenge 49
+    #[test]
+    fn t_cbc_mac() {
+        let key = string_to_bytes("YELLOW SUBMARINE");
+        let iv = vec![0u8; 16];
+        let mac = cbc_mac(b"foobarbazquxfoo!", &key, &iv);
+        let encrypted =
+            encrypt_aes_128_cbc(&pkcs7_pad(b"foobarbazquxfoo!".to_vec(), 16), &key, &iv);
+        assert_eq!(mac, encrypted[16..]);
+        assert!(verify_cbc_mac(b"foobarbazquxfoo!", &key, &iv, &mac));
+        assert!(!verify_cbc_mac(b"foobarbazquxfoo?", &key, &iv, &mac));
+    }
+
+    #[test]
+    fn t_forge_cbc_mac_iv() {
+        let key = rand_bytes();
+        let iv = rand_bytes();
+        // the attacker (account 3) gets a legitimate request for a transfer to themselves signed
+        let message = format_transfer_request(3, 3, 1000000);
+        let mac = cbc_mac(&message, &key, &iv);
+        let forged_message = format_transfer_request(1, 3, 1000000);
+        assert_eq!(&forged_message[..], b"from=1&to=3&amount=1000000");
+        let forged_iv = forge_cbc_mac_iv(&message, &iv, &forged_message);
+        assert!(verify_cbc_mac(&forged_message, &key, &forged_iv, &mac));
+        assert!(!verify_cbc_mac(&forged_message, &key, &iv, &mac));
+    }
+
+    #[test]
+    fn t_cbc_mac_length_extension() {
+        let key = rand_bytes();
+        let iv = vec![0u8; 16];
+        // captured from the victim (account 1)
+        let message = format_multi_transfer_request(1, &[(2, 100), (4, 250)]);
+        assert_eq!(&message[..], b"from=1&tx_list=2:100;4:250");
+        let mac = cbc_mac(&message, &key, &iv);
+        // signed for the attacker's own account; only its first block gets replaced
+        let extension = format_multi_transfer_request(3, &[(3, 0), (3, 1000000)]);
+        let extension_mac = cbc_mac(&extension, &key, &iv);
+        let forged = cbc_mac_length_extension(&message, &mac, &extension, &iv);
+        assert!(forged.starts_with(b"from=1&tx_list=2:100;4:250"));
+        assert!(forged.ends_with(b";3:1000000"));
+        assert!(verify_cbc_mac(&forged, &key, &iv, &extension_mac));
+    }
+
+    // Challenge 50
+    #[test]
+    fn t_cbc_mac_hash() {
+        let hash = cbc_mac_hash(b"alert('MZA who was that?');\n");
+        assert_eq!(bytes_to_hex(hash), "296b8d7cb78a243dda4d0a61d33bbdd1");
+    }
+
+    #[test]
+    fn t_forge_javascript_collision() {
+        let target = b"alert('MZA who was that?');\n";
+        let forged = forge_javascript_collision(target, b"alert('Ayo, the Wu is back!');");
+        assert!(forged.starts_with(b"alert('Ayo, the Wu is back!');//"));
+        assert!(forged.ends_with(b"as that?');\n"));
+        // everything up to the original newline stays inside the comment
+        assert_eq!(
+            forged.iter().filter(|&&b| b == b'\n' || b == b'\r').count(),
+            1
+        );
+        assert_eq!(cbc_mac_hash(&forged), cbc_mac_hash(target));
+    }
+
+    #[test]
+    fn t_aes_128_ctr() {
+        let input_bytes = b64_to_bytes(
+            "L77na/nrFsKvynd6HzOoG7GHTLXsTVu9qvY/2syLXzhPweyyMTJULu/6/kXX0KSvoOLSFQ==",
+        );
+        let key = string_to_bytes("YELLOW SUBMARINE");
+        let decrypted = aes_128_ctr(&input_bytes, &key, 0);
+        assert_eq!(
+            String::from_utf8(decrypted.clone()).unwrap(),
+            "Yo, VIP Let's kick it Ice, Ice, baby Ice, Ice, baby "
+        );
+        assert_eq!(aes_128_ctr(&decrypted, &key, 0), input_bytes);
+    }
+
+    // Challenge 51
+    #[test]
+    fn t_compression_oracle() {
+        let oracle = CompressionOracle::new(b"abc", CompressionOracleCipher::Ctr);
+        assert_eq!(
+            oracle.format_request(b"hi"),
+            b"POST / HTTP/1.1\nHost: hapless.com\nCookie: sessionid=abc\nContent-Length: 2\nhi"
+        );
+        assert_eq!(
+            oracle.ciphertext_len(b"hi"),
+            deflate(&oracle.format_request(b"hi")).len()
+        );
+        let cbc_oracle = CompressionOracle::new(b"abc", CompressionOracleCipher::Cbc);
+        assert_eq!(cbc_oracle.ciphertext_len(b"hi") % 16, 0);
+    }
+
+    #[test]
+    fn t_crime_attack_ctr() {
+        let session_id = b"TmV2ZXIgcmV2ZWFsIHRoZSBXdS1UYW5nIFNlY3JldCE=";
+        let oracle = CompressionOracle::new(session_id, CompressionOracleCipher::Ctr);
+        let alphabet = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/=";
+        let recovered = crime_attack(
+            |body| oracle.ciphertext_len(body),
+            b"sessionid=",
+            alphabet,
+            b'\n',
+            64,
+        );
+        assert_eq!(recovered, session_id);
+    }
+
+    #[test]
+    fn t_crime_attack_cbc() {
+        let session_id = b"TmV2ZXIgcmV2ZWFsIHRoZSBXdS1UYW5nIFNlY3JldCE=";
+        let oracle = CompressionOracle::new(session_id, CompressionOracleCipher::Cbc);
+        let alphabet = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/=";
+        let recovered = crime_attack(
+            |body| oracle.ciphertext_len(body),
+            b"sessionid=",
+            alphabet,
+            b'\n',
+            64,
+        );
+        assert_eq!(recovered, session_id);
+    }
+
+    // Challenge 52
+    #[test]
+    fn t_weak_hash() {
+        let hash = WeakHash::new(16);
+        assert_eq!(hash.initial_state().len(), 2);
+        assert_eq!(hash.hash(b"hi mom").len(), 2);
+        assert_eq!(hash.hash(b"hi mom"), hash.hash(b"hi mom"));
+        assert_eq!(WeakHash::new(24).hash(b"hi mom").len(), 3);
+        assert_ne!(
+            WeakHash::new(16).initial_state(),
+            WeakHash::with_iv_seed(16, 1).initial_state()
+        );
+        assert_eq!(hash.pad(0).len(), 16);
+        assert_eq!(hash.pad(7).len(), 9);
+        assert_eq!(hash.pad(8).len(), 24);
+    }
+
+    #[test]
+    fn t_joux_multicollision() {
+        let hash = WeakHash::new(16);
+        let multicollision = joux_multicollision(&hash, &hash.initial_state(), 4);
+        assert_eq!(multicollision.message_count(), 16);
+        let mut messages: Vec<Vec<u8>> = multicollision.messages().collect();
+        let digest = hash.hash(&messages[0]);
+        assert!(messages.iter().all(|m| hash.hash(m) == digest));
+        messages.sort();
+        messages.dedup();
+        assert_eq!(messages.len(), 16);
+    }
+
+    #[test]
+    fn t_find_cascade_collision() {
+        let cheap = WeakHash::with_iv_seed(16, 1);
+        let expensive = WeakHash::with_iv_seed(24, 2);
+        let (a, b) = find_cascade_collision(&cheap, &expensive);
+        assert_ne!(a, b);
+        assert_eq!(
+            cascade_hash(&cheap, &expensive, &a),
+            cascade_hash(&cheap, &expensive, &b)
+        );
+    }
+
+    // Challenge 53
+    #[test]
+    fn t_expandable_message() {
+        let hash = WeakHash::new(16);
+        let expandable = expandable_message(&hash, &hash.initial_state(), 4);
+        assert_eq!(expandable.min_blocks(), 4);
+        assert_eq!(expandable.max_blocks(), 19);
+        for blocks in 4..=19 {
+            let message = expandable.message(blocks);
+            assert_eq!(message.len(), blocks * 16);
+            assert_eq!(
+                hash.hash_blocks(&hash.initial_state(), &message),
+                expandable.final_state
+            );
+        }
+    }
+
+    #[test]
+    fn t_second_preimage() {
+        for state_bits in [16, 24] {
+            let hash = WeakHash::new(state_bits);
+            let message: Vec<u8> = (0..1024u64)
+                .flat_map(|n| block_from_counter(7, n))
+                .collect();
+            let forged = second_preimage(&hash, &message).unwrap();
+            assert_ne!(forged, message);
+            assert_eq!(forged.len(), message.len());
+            assert_eq!(hash.hash(&forged), hash.hash(&message));
+        }
+    }
+
+    // Challenge 54
+    #[test]
+    fn t_herding() {
+        let hash = WeakHash::new(16);
+        let diamond = build_diamond_structure(&hash, 6);
+        assert_eq!(diamond.depth(), 6);
+        assert_eq!(diamond.levels[0].len(), 64);
+        let prediction = diamond.prediction(&hash, 2);
+        // two whole blocks
+        let prefix = b"Astros 4-3 Dodgers, 7-0 Yankees.";
+        let herded = diamond.herd(&hash, prefix);
+        assert!(herded.starts_with(prefix));
+        assert_eq!(hash.hash(&herded), prediction);
+    }
+
+    fn assert_collision<F: Fn(u64) -> u64>(f: &F, collision: &Collision) {
+        assert_ne!(collision.a, collision.b);
+        assert_eq!(f(collision.a), collision.value);
+        assert_eq!(f(collision.b), collision.value);
+    }
+
+    #[test]
+    fn t_iterated_function() {
+        let f = iterated_function(truncated_aes_hash, 24);
+        let digest = truncated_aes_hash(&5u64.to_be_bytes());
+        let expected = u64::from_be_bytes(digest[..8].try_into().unwrap()) >> 40;
+        assert_eq!(f(5), expected);
+        assert!((0..1000).all(|x| f(x) <= mask(24)));
+        let f = iterated_function(truncated_aes_hash, 64);
+        assert_eq!(f(5), u64::from_be_bytes(digest[..8].try_into().unwrap()));
+    }
+
+    #[test]
+    fn t_birthday_collision() {
+        for bits in [24, 32] {
+            let f = iterated_function(truncated_aes_hash, bits);
+            let collision = birthday_collision(&f, 0);
+            assert_collision(&f, &collision);
+            assert_eq!(collision.stored as u64, collision.evaluations - 1);
+        }
+        let hash = WeakHash::new(24);
+        let f = iterated_function(|m: &[u8]| hash.hash(m), 24);
+        let collision = birthday_collision(&f, 0);
+        assert_collision(&f, &collision);
+        assert_eq!(
+            hash.hash(&collision.a.to_be_bytes()),
+            hash.hash(&collision.b.to_be_bytes())
+        );
+    }
+
+    #[test]
+    fn t_floyd_brent_collision() {
+        for bits in [24, 32] {
+            let f = iterated_function(truncated_aes_hash, bits);
+            let floyd = floyd_collision(&f, 1).unwrap();
+            let brent = brent_collision(&f, 1).unwrap();
+            assert_collision(&f, &floyd);
+            assert_collision(&f, &brent);
+            // both walk the same rho, so they find the same collision
+            assert_eq!((floyd.a, floyd.b), (brent.a, brent.b));
+            assert_eq!(floyd.stored, 0);
+        }
+        // a pure cycle has no tail to collide on
+        let f = |x: u64| (x + 1) % 1000;
+        assert_eq!(floyd_collision(f, 3), None);
+        assert_eq!(brent_collision(f, 3), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Output must be 1 to 64 bits.")]
+    fn t_iterated_function_rejects_zero_bits() {
+        let _ = iterated_function(truncated_aes_hash, 0);
+    }
+
+    #[test]
+    fn t_distinguished_point_collision() {
+        // 42 bits is past what the birthday search can hold in memory, at about 2^21 evaluations
+        for bits in [24, 32, 36, 42] {
+            let f = iterated_function(truncated_aes_hash, bits);
+            let collision = distinguished_point_collision(&f, bits, bits / 4, 4, 1);
+            assert_collision(&f, &collision);
+            // far less memory than the naive birthday search
+            assert!(collision.stored < 1 << (bits / 2 - 4));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Need at least one thread.")]
+    fn t_distinguished_point_collision_needs_a_thread() {
+        let f = iterated_function(truncated_aes_hash, 24);
+        distinguished_point_collision(&f, 24, 6, 0, 1);
+    }
+
+    #[test]
+    fn t_gf128() {
+        let mut rng = StdRng::seed_from_u64(34);
+        // x * x^127 = x^128 = x^7 + x^2 + x + 1
+        let x = Gf128(1 << 126);
+        let x127 = Gf128(1);
+        assert_eq!(x * x127, Gf128(0xe1 << 120));
+        assert_eq!(x.pow(128), Gf128(0xe1 << 120));
+        for _ in 0..10 {
+            let a = Gf128(rng.random());
+            let b = Gf128(rng.random());
+            assert_eq!(a * b, b * a);
+            assert_eq!(a * Gf128::ONE, a);
+            assert_eq!(a * a.inverse(), Gf128::ONE);
+            assert_eq!(a.sqrt().square(), a);
+            assert_eq!((a + b) * (a + b), a.square() + b.square());
+        }
+    }
+
+    #[test]
+    fn t_gf128_poly() {
+        let mut rng = StdRng::seed_from_u64(34);
+        let roots: Vec<Gf128> = (0..4).map(|_| Gf128(rng.random())).collect();
+        let linear = |r: Gf128| Gf128Poly::new(vec![r, Gf128::ONE]);
+        let mut f = roots
+            .iter()
+            .fold(Gf128Poly::one(), |f, &r| f.mul(&linear(r)));
+        assert_eq!(f.degree(), Some(4));
+        assert!(roots.iter().all(|&r| f.evaluate(r).is_zero()));
+        let (q, r) = f.divrem(&linear(roots[0]));
+        assert!(r.is_zero());
+        assert_eq!(q.mul(&linear(roots[0])), f);
+        // a repeated root and an irreducible quadratic factor
+        let quadratic = loop {
+            let q = random_gf128_poly(2, &mut rng).add(&Gf128Poly::x().mul(&Gf128Poly::x()));
+            if gf128_poly_roots(&q, &mut rng).is_empty() {
+                break q;
+            }
+        };
+        let ddf = distinct_degree_factorization(&f.mul(&quadratic));
+        assert_eq!(ddf, vec![(f.clone(), 1), (quadratic.clone(), 2)]);
+        f = f.mul(&linear(roots[1])).mul(&quadratic).scale(Gf128(3));
+        let mut expected = roots.clone();
+        expected.sort_by_key(|r| r.0);
+        assert_eq!(gf128_poly_roots(&f, &mut rng), expected);
+    }
+
+    #[test]
+    fn t_aes_128_gcm_nist_vectors() {
+        let p = hex_to_bytes(
+            "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a721c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b391aafd255",
+        );
+        let c = hex_to_bytes(
+            "42831ec2217774244b7221b784d0d49ce3aa212f2c02a4e035c17e2329aca12e21d514b25466931c7d8f6a5aac84aa051ba30b396a0aac973d58e091473f5985",
+        );
+        let key = hex_to_bytes("feffe9928665731c6d6a8f9467308308");
+        let nonce = hex_to_bytes("cafebabefacedbaddecaf888");
+        let aad = hex_to_bytes("feedfacedeadbeeffeedfacedeadbeefabaddad2");
+        let long_nonce = hex_to_bytes(
+            "9313225df88406e555909c5aff5269aa6a7a9538534f7da1e4c303d2a318a728c3c0c95156809539fcf0e2429a6b525416aedbf5a0de6a57a637b39b",
+        );
+        // (key, nonce, aad, plaintext, ciphertext, tag) from test cases 1 to 6 of the GCM spec
+        let cases = vec![
+            (
+                vec![0; 16],
+                vec![0; 12],
+                vec![],
+                vec![],
+                vec![],
+                hex_to_bytes("58e2fccefa7e3061367f1d57a4e7455a"),
+            ),
+            (
+                vec![0; 16],
+                vec![0; 12],
+                vec![],
+                vec![0; 16],
+                hex_to_bytes("0388dace60b6a392f328c2b971b2fe78"),
+                hex_to_bytes("ab6e47d42cec13bdf53a67b21257bddf"),
+            ),
+            (
+                key.clone(),
+                nonce.clone(),
+                vec![],
+                p.clone(),
+                c.clone(),
+                hex_to_bytes("4d5c2af327cd64a62cf35abd2ba6fab4"),
+            ),
+            (
+                key.clone(),
+                nonce.clone(),
+                aad.clone(),
+                p[..60].to_vec(),
+                c[..60].to_vec(),
+                hex_to_bytes("5bc94fbc3221a5db94fae95ae7121a47"),
+            ),
+            (
+                key.clone(),
+                nonce[..8].to_vec(),
+                aad.clone(),
+                p[..60].to_vec(),
+                hex_to_bytes(
+                    "61353b4c2806934a777ff51fa22a4755699b2a714fcdc6f83766e5f97b6c742373806900e49f24b22b097544d4896b424989b5e1ebac0f07c23f4598",
+                ),
+                hex_to_bytes("3612d2e79e3b0785561be14aaca2fccb"),
+            ),
+            (
+                key.clone(),
+                long_nonce,
+                aad.clone(),
+                p[..60].to_vec(),
+                hex_to_bytes(
+                    "8ce24998625615b603a033aca13fb894be9112a5c3a211a8ba262a3cca7e2ca701e4a9a4fba43c90ccdcb281d48c7c6fd62875d2aca417034c34aee5",
+                ),
+                hex_to_bytes("619cc5aefffe0bfa462af43c1699d050"),
+            ),
+        ];
+        for (key, nonce, aad, plaintext, ciphertext, tag) in cases {
+            assert_eq!(
+                aes_128_gcm_encrypt(&plaintext, &key, &nonce, &aad),
+                (ciphertext.clone(), tag.clone())
+            );
+            assert_eq!(
+                aes_128_gcm_decrypt(&ciphertext, &key, &nonce, &aad, &tag),
+                Some(plaintext)
+            );
+            let mut tampered = tag.clone();
+            tampered[0] ^= 1;
+            assert_eq!(
+                aes_128_gcm_decrypt(&ciphertext, &key, &nonce, &aad, &tampered),
+                None
+            );
+        }
+        assert_eq!(
+            gcm_hash_key(&[0; 16]).to_block(),
+            hex_to_bytes("66e94bd4ef8a2c3b884cfa59ca342b2e")
+        );
+    }
+
+    #[test]
+    fn t_gcm_forbidden_attack() {
+        let mut rng = StdRng::seed_from_u64(34);
+        let key = b"YELLOW SUBMARINE";
+        let nonce = b"reused nonce";
+        let plaintexts: [&[u8]; 3] = [
+            b"transfer $100 to account 12345, memo: rent",
+            b"transfer $20 to account 54321",
+            b"the third message under the same nonce, which narrows things down",
+        ];
+        let messages: Vec<GcmMessage> = plaintexts
+            .iter()
+            .enumerate()
+            .map(|(i, plaintext)| {
+                let aad = format!("header {i}").into_bytes();
+                let (ciphertext, tag) = aes_128_gcm_encrypt(plaintext, key, nonce, &aad);
+                GcmMessage {
+                    aad,
+                    ciphertext,
+                    tag,
+                }
+            })
+            .collect();
+        let h = gcm_hash_key(key);
+        assert!(gcm_forbidden_attack(&messages[..2], &mut rng).contains(&h));
+        assert_eq!(gcm_forbidden_attack(&messages, &mut rng), vec![h]);
+        // flip the amount in the first message from $100 to $900 and give it a valid tag
+        let mut ciphertext = messages[0].ciphertext.clone();
+        ciphertext[10] ^= b'1' ^ b'9';
+        let aad = b"forged header".to_vec();
+        let tag = gcm_forge_tag(h, &messages[1], &aad, &ciphertext);
+        let forged = aes_128_gcm_decrypt(&ciphertext, key, nonce, &aad, &tag).unwrap();
+        assert_eq!(forged, b"transfer $900 to account 12345, memo: rent");
+    }
+
+    #[test]
+    fn t_bit_matrix() {
+        let mut rng = StdRng::seed_from_u64(35);
+        let random_rows = |rng: &mut StdRng, rows: usize, cols: usize| -> Vec<Vec<bool>> {
+            (0..rows)
+                .map(|_| (0..cols).map(|_| rng.random()).collect())
+                .collect()
+        };
+        let a = BitMatrix::from_rows(&random_rows(&mut rng, 70, 150));
+        assert_eq!(a.transpose().transpose(), a);
+        assert_eq!(a.mul(&BitMatrix::identity(150)), a);
+        assert_eq!(a.rank(), 70);
+        let kernel = a.kernel();
+        assert_eq!(kernel.len(), 80);
+        assert!(kernel.iter().all(|v| a.mul_vector(v).iter().all(|&b| !b)));
+        assert_eq!(BitMatrix::from_rows(&kernel).rank(), 80);
+        let b = BitMatrix::from_rows(&random_rows(&mut rng, 150, 20));
+        let v: Vec<bool> = (0..20).map(|_| rng.random()).collect();
+        assert_eq!(a.mul(&b).mul_vector(&v), a.mul_vector(&b.mul_vector(&v)));
+        let x = a.solve(&a.mul_vector(&b.column(3))).unwrap();
+        assert_eq!(a.mul_vector(&x), a.mul_vector(&b.column(3)));
+        // a dependent row makes some right hand sides unsolvable
+        let c = a.stack(&a.select_rows(0..1));
+        assert_eq!(c.rank(), 70);
+        let mut rhs = vec![false; 71];
+        rhs[70] = true;
+        assert_eq!(c.solve(&rhs), None);
+    }
+
+    #[test]
+    fn t_gf128_matrices() {
+        let mut rng = StdRng::seed_from_u64(35);
+        let square = gf128_square_matrix();
+        for _ in 0..5 {
+            let a = Gf128(rng.random());
+            let c = Gf128(rng.random());
+            assert_eq!(Gf128::from_bits(&a.to_bits()), a);
+            assert_eq!(square.mul_vector(&a.to_bits()), a.square().to_bits());
+            assert_eq!(
+                gf128_mul_matrix(c).mul_vector(&a.to_bits()),
+                (c * a).to_bits()
+            );
+        }
+    }
+
+    #[test]
+    fn t_aes_128_gcm_truncated_tags() {
+        let key = hex_to_bytes("feffe9928665731c6d6a8f9467308308");
+        let nonce = hex_to_bytes("cafebabefacedbaddecaf888");
+        let (ciphertext, tag) = aes_128_gcm_encrypt(b"short tags", &key, &nonce, b"aad");
+        for tag_len in [4, 8, 12] {
+            let (c, t) =
+                aes_128_gcm_encrypt_with_tag_len(b"short tags", &key, &nonce, b"aad", tag_len);
+            assert_eq!(c, ciphertext);
+            assert_eq!(t, tag[..tag_len]);
+            assert_eq!(
+                aes_128_gcm_decrypt_with_tag_len(&c, &key, &nonce, b"aad", &t, tag_len),
+                Some(b"short tags".to_vec())
+            );
+            assert_eq!(
+                aes_128_gcm_decrypt_with_tag_len(&c, &key, &nonce, b"aad", &tag, tag_len),
+                None
+            );
+        }
+    }
+
+    // Challenge 64
+    #[test]
+    fn t_gcm_ferguson_attack() {
+        // 16-bit tags and 2^9 blocks keep the test quick, and the test below does 32 bits
+        let mut rng = StdRng::seed_from_u64(35);
+        let key = b"YELLOW SUBMARINE";
+        let nonce = b"fixed nonce!";
+        let plaintext = vec![b'A'; 512 * 16];
+        let (ciphertext, tag) = aes_128_gcm_encrypt_with_tag_len(&plaintext, key, nonce, b"", 2);
+        let mut queries = 0;
+        let mut dimensions = vec![];
+        let h = gcm_ferguson_attack(
+            &ciphertext,
+            &tag,
+            |c, t| {
+                queries += 1;
+                aes_128_gcm_decrypt_with_tag_len(c, key, nonce, b"", t, 2).is_some()
+            },
+            |dimension| dimensions.push(dimension),
+            &mut rng,
+        );
+        assert_eq!(h, gcm_hash_key(key));
+        assert_eq!(dimensions.first(), Some(&128));
+        assert_eq!(dimensions.last(), Some(&1));
+        assert!(dimensions.windows(2).all(|w| w[0] > w[1]));
+        // each accepted forgery costs about 2^(16 - zeroed rows) queries
+        assert!(queries < 2000);
+    }
+
+    #[test]
+    fn t_gcm_ferguson_attack_32_bit_tags() {
+        // the full sized attack, 32-bit tags and 2^17 blocks. decrypting 2 MiB for each of the
+        // tens of thousands of queries would take hours, but ghash is linear, so an oracle that
+        // knows H can tell which forgeries the real one would accept from the changed blocks alone
+        let mut rng = StdRng::seed_from_u64(35);
+        let key = b"YELLOW SUBMARINE";
+        let nonce = b"fixed nonce!";
+        let blocks = (1 << 17) - 1;
+        let plaintext = vec![b'A'; blocks * 16];
+        let (ciphertext, tag) = aes_128_gcm_encrypt_with_tag_len(&plaintext, key, nonce, b"", 4);
+        let h = gcm_hash_key(key);
+        // block j of the ciphertext is multiplied by H^(blocks + 1 - j), and the attack only
+        // changes the blocks multiplied by H^(2^i)
+        let changeable: Vec<(usize, Gf128)> = (1..=17)
+            .rev()
+            .map(|i| (blocks + 1 - (1 << i), h.pow(1 << i)))
+            .collect();
+        let mut queries = 0;
+        let recovered = gcm_ferguson_attack(
+            &ciphertext,
+            &tag,
+            |c, t| {
+                queries += 1;
+                assert_eq!(c.len(), ciphertext.len());
+                let mut difference = Gf128::ZERO;
+                for &(block, power) in &changeable {
+                    let start = block * 16;
+                    let delta = xor_bytes(&c[start..start + 16], &ciphertext[start..start + 16]);
+                    difference = difference + Gf128::from_block(&delta) * power;
+                }
+                let valid =
+                    t == tag.as_slice() && difference.to_block()[..t.len()].iter().all(|&b| b == 0);
+                // the attack only learns from accepted forgeries, so those are the ones checked
+                // for changes outside the changeable blocks, which would make the answer wrong
+                if valid {
+                    let mut unchanged = 0;
+                    for &(block, _) in &changeable {
+                        let start = block * 16;
+                        assert_eq!(c[unchanged..start], ciphertext[unchanged..start]);
+                        unchanged = start + 16;
+                    }
+                    assert_eq!(c[unchanged..], ciphertext[unchanged..]);
+                }
+                valid
+            },
+            |_| {},
+            &mut rng,
+        );
+        assert_eq!(recovered, h);
+        // the first forgery alone costs about 2^(32 - 16) queries
+        assert!(queries > 1 << 14);
+    }
+
+    #[test]
+    fn t_mod_sqrt() {
+        let mut rng = StdRng::seed_from_u64(36);
+        // 3 mod 4, 1 mod 8 (so Tonelli-Shanks has to loop) and the cryptopals curve prime
+        for p in [
+            BigUint::from(1_000_003u32),
+            BigUint::from(998_244_353u32),
+            EcGroup::cryptopals().curve.p,
+        ] {
+            for _ in 0..20 {
+                let a = random_biguint_below(&p, &mut rng);
+                let square = &a * &a % &p;
+                let root = mod_sqrt(&square, &p).unwrap();
+                assert_eq!(&root * &root % &p, square);
+                assert!(root == a || root == &p - &a);
+                assert!(root <= &p - &root);
+            }
+        }
+        // 5 is not a square mod 1000003
+        assert_eq!(
+            mod_sqrt(&BigUint::from(5u32), &BigUint::from(1_000_003u32)),
+            None
+        );
+    }
+
+    // Challenge 59
+    #[test]
+    fn t_cryptopals_curve() {
+        let mut rng = StdRng::seed_from_u64(36);
+        let group = EcGroup::cryptopals();
+        let curve = &group.curve;
+        assert!(curve.is_on_curve(&group.g));
+        assert_eq!(curve.scalar_mul(&group.g, &group.n), EcPoint::Infinity);
+        let point = curve.random_point(&mut rng);
+        assert!(curve.is_on_curve(&point));
+        assert_eq!(
+            curve.scalar_mul_projective(&point, &group.order()),
+            EcPoint::Infinity
+        );
+        for _ in 0..5 {
+            let k = random_biguint_below(&group.n, &mut rng);
+            let affine = curve.scalar_mul(&group.g, &k);
+            assert!(curve.is_on_curve(&affine));
+            assert_eq!(curve.scalar_mul_projective(&group.g, &k), affine);
+            let l = random_biguint_below(&group.n, &mut rng);
+            assert_eq!(
+                curve.add(&affine, &curve.scalar_mul(&group.g, &l)),
+                curve.scalar_mul(&group.g, &(&k + &l))
+            );
+        }
+        assert_eq!(
+            curve.add(&group.g, &curve.negate(&group.g)),
+            EcPoint::Infinity
+        );
+        assert_eq!(
+            curve.double(&group.g),
+            curve.scalar_mul(&group.g, &BigUint::from(2u32))
+        );
+    }
+
+    // Challenge 60
+    #[test]
+    fn t_montgomery_ladder() {
+        let mut rng = StdRng::seed_from_u64(36);
+        let montgomery = MontgomeryGroup::cryptopals();
+        let group = EcGroup::cryptopals();
+        let curve = &montgomery.curve;
+        assert_eq!(curve.to_weierstrass(), group.curve);
+        assert_eq!(
+            group.g,
+            group
+                .curve
+                .lift_x(&curve.u_to_weierstrass_x(&montgomery.u))
+                .unwrap()
+        );
+        assert_eq!(curve.ladder(&montgomery.u, &montgomery.n), BigUint::zero());
+        for _ in 0..5 {
+            let k = random_biguint_below(&group.n, &mut rng);
+            let EcPoint::Affine(x, _) = group.curve.scalar_mul_projective(&group.g, &k) else {
+                panic!("kG is infinity");
+            };
+            assert_eq!(
+                curve.ladder(&montgomery.u, &k),
+                curve.weierstrass_x_to_u(&x)
+            );
+        }
+        // the factorisation of the twist order given in the challenge
+        let factors: [u64; 9] = [
+            4,
+            11,
+            107,
+            197,
+            1621,
+            105143,
+            405373,
+            2323367,
+            1571528514013,
+        ];
+        let twist_order: BigUint = factors.iter().map(|&f| BigUint::from(f)).product();
+        assert_eq!(montgomery.twist_order(), twist_order);
+    }
+
+    #[test]
+    fn t_p256() {
+        let group = EcGroup::p256();
+        let curve = &group.curve;
+        assert!(curve.is_on_curve(&group.g));
+        assert_eq!(
+            curve.scalar_mul_projective(&group.g, &group.n),
+            EcPoint::Infinity
+        );
+        let multiples = [
+            (
+                "7cf27b188d034f7e8a52380304b51ac3c08969e277f21b35a60b48fc47669978",
+                "07775510db8ed040293d9ac69f7430dbba7dade63ce982299e04b79d227873d1",
+            ),
+            (
+                "5ecbe4d1a6330a44c8f7ef951d4bf165e6c6b721efada985fb41661bc6e7fd6c",
+                "8734640c4998ff7e374b06ce1a64a2ecd82ab036384fb83d9a79b127a27d5032",
+            ),
+        ];
+        for (k, (x, y)) in (2u32..).zip(multiples) {
+            let expected = EcPoint::Affine(hex_to_biguint(x), hex_to_biguint(y));
+            assert_eq!(curve.scalar_mul(&group.g, &BigUint::from(k)), expected);
+            assert_eq!(
+                curve.scalar_mul_projective(&group.g, &BigUint::from(k)),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn t_ec_point_encoding() {
+        let mut rng = StdRng::seed_from_u64(36);
+        for group in [EcGroup::cryptopals(), EcGroup::p256()] {
+            let curve = &group.curve;
+            for _ in 0..5 {
+                let point = curve.random_point(&mut rng);
+                let uncompressed = curve.encode_point(&point, false);
+                let compressed = curve.encode_point(&point, true);
+                assert_eq!(uncompressed.len(), 1 + 2 * curve.field_len());
+                assert_eq!(compressed.len(), 1 + curve.field_len());
+                assert_eq!(curve.decode_point(&uncompressed), Some(point.clone()));
+                assert_eq!(curve.decode_point(&compressed), Some(point));
+                let mut off_curve = uncompressed.clone();
+                *off_curve.last_mut().unwrap() ^= 1;
+                assert_eq!(curve.decode_point(&off_curve), None);
+                assert_eq!(curve.decode_point(&compressed[1..]), None);
+            }
+            assert_eq!(curve.encode_point(&EcPoint::Infinity, true), vec![0]);
+            assert_eq!(curve.decode_point(&[0]), Some(EcPoint::Infinity));
+        }
+        let group = EcGroup::p256();
+        assert_eq!(
+            bytes_to_hex(group.curve.encode_point(&group.g, true)),
+            "036b17d1f2e12c4247f8bce6e563a440f277037d812deb33a0f4a13945d898c296"
+        );
+    }
+
+    #[test]
+    fn t_ecdh() {
+        let mut rng = StdRng::seed_from_u64(36);
+        for group in [EcGroup::cryptopals(), EcGroup::p256()] {
+            let (alice_public, alice_private) = generate_ec_keypair(&group, &mut rng);
+            let (bob_public, bob_private) = generate_ec_keypair(&group, &mut rng);
+            let alice_secret = ecdh_shared_secret(&alice_private, &bob_public.q).unwrap();
+            let bob_secret = ecdh_shared_secret(&bob_private, &alice_public.q).unwrap();
+            assert_eq!(alice_secret, bob_secret);
+            assert_eq!(ecdh_shared_secret(&alice_private, &EcPoint::Infinity), None);
+            let EcPoint::Affine(x, y) = &bob_public.q else {
+                panic!("public key is infinity");
+            };
+            let off_curve = EcPoint::Affine(x.clone(), (y + 1u32) % &group.curve.p);
+            assert_eq!(ecdh_shared_secret(&alice_private, &off_curve), None);
+        }
+    }
+
+    #[test]
+    fn t_ecdsa() {
+        let mut rng = StdRng::seed_from_u64(36);
+        // RFC 6979 A.2.5, P-256 with SHA-256 and the message "sample"
+        let group = EcGroup::p256();
+        let d = hex_to_biguint("c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721");
+        let private_key = EcPrivateKey {
+            group: group.clone(),
+            d: d.clone(),
+        };
+        let public_key = EcPublicKey {
+            group: group.clone(),
+            q: group.curve.scalar_mul_projective(&group.g, &d),
+        };
+        assert_eq!(
+            public_key.q,
+            EcPoint::Affine(
+                hex_to_biguint("60fed4ba255a9d31c961eb74c6356d68c049b8923b61fa6ce669622e60f29fb6"),
+                hex_to_biguint("7903fe1008b8bc99a41ae9e95628bc64f2f1b20c2d7e9f5177a3c294d4462299"),
+            )
+        );
+        let k = hex_to_biguint("a6e3c57dd01abe90086538398355dd4c3b17aa873382b0f24d6129493d8aad60");
+        let signature = ecdsa_sign_with_nonce(b"sample", &private_key, &k).unwrap();
+        assert_eq!(
+            signature,
+            EcdsaSignature {
+                r: hex_to_biguint(
+                    "efd48b2aacb6a8fd1140dd9cd45e81d69d2c877b56aaf991c34d0ea84eaf3716"
+                ),
+                s: hex_to_biguint(
+                    "f7cb1c942d657c41d436c7a1b6e29f65f3e900dbb9aff4064dc4ab2f843acda8"
+                ),
+            }
+        );
+        assert!(ecdsa_verify(b"sample", &signature, &public_key));
+        assert!(!ecdsa_verify(b"test", &signature, &public_key));
+        for group in [EcGroup::cryptopals(), EcGroup::p256()] {
+            let (public_key, private_key) = generate_ec_keypair(&group, &mut rng);
+            let signature = ecdsa_sign(b"hi mom", &private_key, &mut rng);
+            assert!(ecdsa_verify(b"hi mom", &signature, &public_key));
+            assert!(!ecdsa_verify(b"hi dad", &signature, &public_key));
+            // (r, n - s) is the other valid signature, but nothing else nearby is
+            let negated = EcdsaSignature {
+                r: signature.r.clone(),
+                s: &group.n - &signature.s,
+            };
+            assert!(ecdsa_verify(b"hi mom", &negated, &public_key));
+            let bad = EcdsaSignature {
+                r: signature.r.clone(),
+                s: (&signature.s + 1u32) % &group.n,
+            };
+            assert!(!ecdsa_verify(b"hi mom", &bad, &public_key));
+        }
+    }
+
+    #[test]
+    fn t_small_prime_factors() {
+        let n = BigUint::from(2u64 * 2 * 3 * 7 * 7 * 101 * 65537 * 1_000_003);
+        assert_eq!(small_prime_factors(&n, 1000), vec![2, 3, 7, 101]);
+        assert_eq!(small_prime_factors(&n, 100_000), vec![2, 3, 7, 101, 65537]);
+    }
+
+    // Challenge 59
+    #[test]
+    fn t_ecdh_invalid_curve_attack() {
+        let mut rng = StdRng::seed_from_u64(37);
+        let group = EcGroup::cryptopals();
+        let weak_curves = cryptopals_invalid_curves();
+        for (curve, order) in &weak_curves {
+            assert_eq!(curve.a, group.curve.a);
+            let point = curve.random_point(&mut rng);
+            assert_eq!(
+                curve.scalar_mul_projective(&point, order),
+                EcPoint::Infinity
+            );
+        }
+        let (_, private_key) = generate_ec_keypair(&group, &mut rng);
+        let bob = EcdhMacOracle::new(private_key.clone());
+        let mut queries = 0;
+        let d = ecdh_invalid_curve_attack(
+            &group,
+            &weak_curves,
+            |point| {
+                queries += 1;
+                bob.respond(point)
+            },
+            1 << 16,
+            &mut rng,
+        );
+        assert_eq!(d, Some(private_key.d));
+        assert!(queries < 20);
+        // not enough small factors to cover the whole key
+        assert_eq!(
+            ecdh_invalid_curve_attack(
+                &group,
+                &weak_curves[..1],
+                |p| bob.respond(p),
+                1 << 16,
+                &mut rng
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn t_ec_kangaroo() {
+        let mut rng = StdRng::seed_from_u64(37);
+        let group = EcGroup::cryptopals();
+        let bound = BigUint::one() << 24;
+        for _ in 0..3 {
+            let k = random_biguint_below(&bound, &mut rng);
+            let y = group.curve.scalar_mul_projective(&group.g, &k);
+            assert_eq!(ec_kangaroo(&group.curve, &group.g, &y, &bound), Some(k));
+        }
+        // 2^30 G is well outside [0, 2^24)
+        let y = group
+            .curve
+            .scalar_mul_projective(&group.g, &(BigUint::one() << 30));
+        assert_eq!(ec_kangaroo(&group.curve, &group.g, &y, &bound), None);
+    }
+
+    // Challenge 60
+    #[test]
+    fn t_ecdh_twist_attack() {
+        let mut rng = StdRng::seed_from_u64(37);
+        let group = MontgomeryGroup::cryptopals();
+        // twist factors below 2^17 leave about 2^80 possibilities for a full key, far too many
+        // for the kangaroo in a test, so Bob's key is kept below 2^64
+        let d_bound = BigUint::one() << 64;
+        let d = random_biguint_below(&d_bound, &mut rng);
+        let bob = MontgomeryMacOracle::new(group.clone(), d.clone());
+        let public_u = bob.public_u();
+        assert!(!group.curve.is_on_twist(&public_u));
+        let recovered = ecdh_twist_attack(
+            &group,
+            &public_u,
+            |u| {
+                assert!(group.curve.is_on_twist(u));
+                bob.respond(u)
+            },
+            1 << 17,
+            &d_bound,
+            &mut rng,
+        );
+        assert_eq!(recovered, Some(d));
+    }
+
+    // Challenge 57
+    #[test]
+    fn t_dh_subgroup_confinement_attack() {
+        let mut rng = StdRng::seed_from_u64(38);
+        let params = DhParams::challenge_57();
+        assert_eq!(
+            small_prime_factors(&params.cofactor(), 1 << 16),
+            vec![
+                2, 3, 5, 109, 7963, 8539, 20641, 38833, 39341, 46337, 51977, 54319, 57529
+            ]
+        );
+        let h = find_element_of_order(&params, 7963, &mut rng);
+        assert!(!h.is_one());
+        assert!(h.modpow(&BigUint::from(7963u32), &params.p).is_one());
+        let (public_key, private_key) = generate_dh_keypair(&params, &mut rng);
+        let bob = DhMacOracle::new(private_key.clone());
+        let mut queries = 0;
+        let (x, m) = dh_subgroup_confinement_attack(
+            &params,
+            |h| {
+                queries += 1;
+                bob.respond(h)
+            },
+            1 << 16,
+            &mut rng,
+        );
+        assert!(m > params.q);
+        assert_eq!(x, private_key.x);
+        assert_eq!(params.g.modpow(&x, &params.p), public_key.y);
+        assert!(queries <= 13);
+    }
+
+    #[test]
+    fn t_kangaroo() {
+        let mut rng = StdRng::seed_from_u64(38);
+        let params = DhParams::challenge_58();
+        let (lower, upper) = (BigUint::from(1u32 << 30), BigUint::from(1u32 << 31));
+        let width = &upper - &lower;
+        let default = KangarooParams::for_width(&width);
+        let tuned = KangarooParams {
+            jumps: default.jumps + 2,
+            trap_factor: 8,
+        };
+        for kangaroo_params in [&default, &tuned] {
+            let k = random_biguint_range(&lower, &upper, &mut rng);
+            let y = params.g.modpow(&k, &params.p);
+            assert_eq!(
+                kangaroo(&params, &params.g, &y, &lower, &upper, kangaroo_params),
+                Some(k)
+            );
+        }
+        // below the interval the wild kangaroo never reaches the trap
+        let y = params.g.modpow(&BigUint::from(12345u32), &params.p);
+        assert_eq!(
+            kangaroo(&params, &params.g, &y, &lower, &upper, &default),
+            None
+        );
+    }
+
+    // Challenge 58
+    #[test]
+    fn t_dh_kangaroo_attack() {
+        let mut rng = StdRng::seed_from_u64(38);
+        let params = DhParams::challenge_58();
+        // the small factors of j fall about 40 bits short of q
+        let m: BigUint = small_prime_factors(&params.cofactor(), 1 << 16)
+            .into_iter()
+            .map(BigUint::from)
+            .product();
+        assert_eq!((&params.q / &m).bits(), 40);
+        let (public_key, private_key) = generate_dh_keypair(&params, &mut rng);
+        let bob = DhMacOracle::new(private_key.clone());
+        assert_eq!(
+            dh_kangaroo_attack(&public_key, |h| bob.respond(h), 1 << 16, &mut rng),
+            Some(private_key.x)
+        );
+    }
+
+    // Challenge 61
+    #[test]
+    fn t_ecdsa_dsks() {
+        let mut rng = StdRng::seed_from_u64(39);
+        for group in [EcGroup::cryptopals(), EcGroup::p256()] {
+            let (public_key, private_key) = generate_ec_keypair(&group, &mut rng);
+            let signature = ecdsa_sign(b"hi mom", &private_key, &mut rng);
+            let (eve_public, eve_private) =
+                ecdsa_dsks(b"hi mom", &signature, &public_key, &mut rng);
+            assert_ne!(eve_public.group.g, group.g);
+            assert!(eve_public.group.curve.is_on_curve(&eve_public.group.g));
+            assert!(ecdsa_verify(b"hi mom", &signature, &eve_public));
+            assert!(!ecdsa_verify(b"hi dad", &signature, &eve_public));
+            // Eve's key pair works for her own signatures too
+            let own = ecdsa_sign(b"hi dad", &eve_private, &mut rng);
+            assert!(ecdsa_verify(b"hi dad", &own, &eve_public));
+            assert!(!ecdsa_verify(b"hi dad", &own, &public_key));
+        }
+    }
+
+    #[test]
+    fn t_lll() {
+        let rational = |rows: &[&[i64]]| -> Vec<Vec<BigRational>> {
+            rows.iter()
+                .map(|row| {
+                    integer_vector(&row.iter().map(|&x| BigInt::from(x)).collect::<Vec<_>>())
+                })
+                .collect()
+        };
+        assert_eq!(
+            lll(&rational(&[&[1, 1, 1], &[-1, 0, 2], &[3, 5, 6]])),
+            rational(&[&[0, 1, 0], &[1, 0, 1], &[-1, 0, 2]])
+        );
+        assert_eq!(
+            lll(&rational(&[
+                &[105, 821, 404, 328],
+                &[881, 667, 644, 927],
+                &[181, 483, 87, 500],
+                &[893, 834, 732, 441]
+            ])),
+            rational(&[
+                &[88, -171, -229, -314],
+                &[269, 312, -142, 186],
+                &[76, -338, -317, 172],
+                &[519, -299, 470, -73]
+            ])
+        );
+        let fraction = |n: i64, d: i64| BigRational::new(n.into(), d.into());
+        assert_eq!(
+            lll(&[
+                vec![fraction(1, 2), fraction(1, 3)],
+                vec![fraction(3, 4), fraction(5, 7)]
+            ]),
+            vec![
+                vec![fraction(-1, 4), fraction(1, 21)],
+                vec![fraction(0, 1), fraction(3, 7)]
+            ]
+        );
+    }
+
+    // Challenge 62
+    #[test]
+    fn t_ecdsa_biased_nonce_attack() {
+        let mut rng = StdRng::seed_from_u64(39);
+        let group = EcGroup::cryptopals();
+        let (public_key, private_key) = generate_ec_keypair(&group, &mut rng);
+        let signed: Vec<(Vec<u8>, EcdsaSignature)> = (0..12)
+            .map(|i| {
+                let message = format!("message {i}").into_bytes();
+                let signature = ecdsa_sign_biased(&message, &private_key, 16, &mut rng);
+                assert!(ecdsa_verify(&message, &signature, &public_key));
+                (message, signature)
+            })
+            .collect();
+        assert_eq!(
+            ecdsa_biased_nonce_attack(&signed, &public_key, 16),
+            Some(private_key.d)
+        );
+        // 16 bits from each of 5 signatures is well short of the size of the key
+        assert_eq!(
+            ecdsa_biased_nonce_attack(&signed[..5], &public_key, 16),
+            None
+        );
+    }
+
+    #[test]
+    fn t_lll_with_delta() {
+        let rational = |rows: &[[i64; 4]]| -> Vec<Vec<BigRational>> {
+            rows.iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|&x| BigRational::from_integer(x.into()))
+                        .collect()
+                })
+                .collect()
+        };
+        let basis = rational(&[
+            [105, 821, 404, 328],
+            [881, 667, 644, 927],
+            [181, 483, 87, 500],
+            [893, 834, 732, 441],
+        ]);
+        let half = BigRational::new(1.into(), 2.into());
+        let reduced = lll_with_delta(&basis, &half);
+        assert_eq!(
+            reduced,
+            rational(&[
+                [76, -338, -317, 172],
+                [88, -171, -229, -314],
+                [269, 312, -142, 186],
+                [519, -299, 470, -73]
+            ])
+        );
+        assert_eq!(lll_with_delta(&basis, &half), reduced);
+        assert!(is_lll_reduced(&reduced, &half));
+        // the weaker reduction is not good enough for delta = 99/100
+        let delta = BigRational::new(99.into(), 100.into());
+        assert!(!is_lll_reduced(&reduced, &delta));
+        assert!(is_lll_reduced(&lll(&basis), &delta));
+        assert!(!is_lll_reduced(&basis, &half));
+    }
+
+    #[test]
+    fn t_gram_schmidt() {
+        let fraction = |n: i64, d: i64| BigRational::new(n.into(), d.into());
+        let basis = vec![
+            vec![fraction(3, 1), fraction(1, 1)],
+            vec![fraction(2, 1), fraction(2, 1)],
+        ];
+        let GramSchmidt { orthogonal, mu } = gram_schmidt(&basis);
+        assert_eq!(
+            orthogonal,
+            vec![
+                vec![fraction(3, 1), fraction(1, 1)],
+                vec![fraction(-2, 5), fraction(6, 5)]
+            ]
+        );
+        assert_eq!(mu[1][0], fraction(4, 5));
+        assert!(dot(&orthogonal[0], &orthogonal[1]).is_zero());
+    }
+
+    #[test]
+    fn t_closest_vector() {
+        let fraction = |n: i64, d: i64| BigRational::new(n.into(), d.into());
+        let basis: Vec<Vec<BigRational>> = [[3, 1, 4], [1, 5, 9], [2, 6, 5]]
+            .iter()
+            .map(|row| row.iter().map(|&x| fraction(x, 1)).collect())
+            .collect();
+        let target = vec![fraction(27, 2), fraction(-7, 3), fraction(40, 1)];
+        // 7 * (3, 1, 4) + 4 * (1, 5, 9) - 5 * (2, 6, 5), by exhaustive search
+        let closest = vec![fraction(15, 1), fraction(-3, 1), fraction(39, 1)];
+        assert_eq!(babai_nearest_plane(&basis, &target), closest);
+        assert_eq!(
+            cvp_embedding(&basis, &target, &fraction(1, 1)),
+            Some(closest.clone())
+        );
+        // a lattice point is its own closest vector
+        assert_eq!(babai_nearest_plane(&basis, &closest), closest);
+        // a weaker reduction still finds it here
+        let half = fraction(1, 2);
+        assert_eq!(
+            babai_nearest_plane_with_delta(&basis, &target, &half),
+            closest
+        );
+        assert_eq!(
+            cvp_embedding_with_delta(&basis, &target, &fraction(1, 1), &half),
+            Some(closest.clone())
+        );
+    }
+
+    fn lyrics_corpus() -> Vec<u8> {
+        // the plaintext of challenge 7, a few kilobytes of English lyrics
+        decrypt_aes_128_ecb(
+            &b64_file_to_bytes("./challenge_files/7.txt"),
+            b"YELLOW SUBMARINE",
+        )
+    }
+
+    #[test]
+    fn t_scorers() {
+        let english = b"the quick brown fox jumps over the lazy dog".to_vec();
+        let garbled = xor_bytes(&english, &[0xc3]);
+        let corpus = lyrics_corpus();
+        let scorers: Vec<Box<dyn Scorer>> = vec![
+            Box::new(LetterTableScorer),
+            Box::new(ChiSquaredScorer::english()),
+            Box::new(ChiSquaredScorer::from_corpus(&corpus, true)),
+            Box::new(NgramScorer::from_corpus(&corpus, 1)),
+            Box::new(NgramScorer::from_corpus(&corpus, 2)),
+            Box::new(NgramScorer::from_corpus(&corpus, 3)),
+            Box::new(PrintableScorer),
+        ];
+        for scorer in &scorers {
+            assert!(scorer.score(&english) > scorer.score(&garbled));
+        }
+        // empty and one byte inputs get real scores, and a NaN would outrank everything
+        for scorer in scorers
+            .iter()
+            .map(|scorer| scorer.as_ref())
+            .chain([&FileTypeScorer as &dyn Scorer])
+        {
+            assert!(!scorer.score(b"").is_nan());
+            assert!(!scorer.score(b"e").is_nan());
+        }
+        assert_eq!(ChiSquaredScorer::english().score(b""), 0.0);
+        let trigrams = NgramScorer::from_corpus(&corpus, 3);
+        assert!(trigrams.score(b"th") < trigrams.score(b"the"));
+        assert_eq!(trigrams.score(b""), trigrams.score(b"\xff\xfe\xfd"));
+        assert_eq!(LetterTableScorer.score(b"eta"), 296.0);
+        assert_eq!(PrintableScorer.score(b"ab\x00\xff"), 0.5);
+        // bigrams know which order letters come in, unigrams do not
+        let bigrams = NgramScorer::from_corpus(&corpus, 2);
+        let unigrams = NgramScorer::from_corpus(&corpus, 1);
+        assert!(bigrams.score(b"the") > bigrams.score(b"hte"));
+        assert_eq!(unigrams.score(b"the"), unigrams.score(b"hte"));
+    }
+
+    #[test]
+    fn t_break_xor_with_scorers() {
+        let input_bytes =
+            hex_to_bytes("1b37373331363f78151b7f2b783431333d78397828372d363c78373e783a393b3736");
+        let keys_plaintexts = brute_single_byte_xor_cipher(&input_bytes);
+        let chi_squared = ChiSquaredScorer::english();
+        let best = highest_scoring_plaintext_with(&keys_plaintexts, &chi_squared);
+        assert_eq!(best.1, b"Cooking MC's like a pound of bacon");
+        // digits xored with 0x40 are letters, which the letter table prefers to the real
+        // plaintext, but a model trained on similar data is not fooled
+        let readings = b"3.14159, 2.71828, 1.41421, 1.73205, 0.57721, 4.66920";
+        let corpus = b"1.61803, 2.50290, 0.91596, 1.20205, 0.26149, 3.35988, 1.32471";
+        let ciphertext = xor_bytes(readings, &[0x5a]);
+        let keys_plaintexts = brute_single_byte_xor_cipher(&ciphertext);
+        assert_ne!(highest_scoring_plaintext(&keys_plaintexts).1, readings);
+        let trained = NgramScorer::from_corpus(corpus, 2);
+        let best = highest_scoring_plaintext_with(&keys_plaintexts, &trained);
+        assert_eq!((best.0, best.1), (vec![0x5a], readings.to_vec()));
+        let mut rng = StdRng::seed_from_u64(41);
+        let lines: Vec<Vec<u8>> = (0..20)
+            .map(|_| (0..readings.len()).map(|_| rng.random()).collect())
+            .chain([ciphertext.clone()])
+            .collect();
+        let best = break_single_character_xor_with(lines, &trained);
+        assert_eq!(best.1, readings);
+        let ciphertext = xor_bytes(&readings.repeat(4), b"KEY");
+        let unigrams = ChiSquaredScorer::from_corpus(corpus, false);
+        let (key, plaintext) = break_repeating_key_xor_with(&ciphertext, vec![2, 3, 4], &unigrams);
+        assert_eq!(key, b"KEY");
+        assert_eq!(plaintext, readings.repeat(4));
+    }
+
+    #[test]
+    fn t_language_model() {
+        let corpus = lyrics_corpus();
+        let mut model = LanguageModel::new();
+        model.train(b"abab");
+        assert_eq!(model.counts(1)[b"a".as_slice()], 2);
+        assert_eq!(model.counts(2)[b"ab".as_slice()], 2);
+        assert_eq!(model.counts(2)[b"ba".as_slice()], 1);
+        assert_eq!(model.counts(3).len(), 2);
+        // reading in chunks counts the n-grams across chunk boundaries too
+        let long = corpus.repeat(30);
+        assert!(long.len() > 1 << 16);
+        let mut expected = LanguageModel::new();
+        expected.train(&long);
+        let trained = LanguageModel::train_from_reader(long.as_slice()).unwrap();
+        assert_eq!(trained, expected);
+        // the serialised form round trips and rejects anything else
+        let bytes = trained.to_bytes();
+        assert_eq!(LanguageModel::from_bytes(&bytes), Some(trained.clone()));
+        assert_eq!(LanguageModel::from_bytes(&bytes[..bytes.len() - 1]), None);
+        assert_eq!(
+            LanguageModel::from_bytes(&[bytes.as_slice(), b"x"].concat()),
+            None
+        );
+        assert_eq!(LanguageModel::from_bytes(b"not a model"), None);
+        let pruned = trained.pruned(100);
+        assert!(pruned.counts(3).len() < trained.counts(3).len());
+        assert!(pruned.counts(3).values().all(|&count| count >= 100));
+        let path = std::env::temp_dir().join("cryptopals_language_model.bin");
+        let path = path.to_str().unwrap();
+        pruned.save(path).unwrap();
+        assert_eq!(LanguageModel::load(path).unwrap(), pruned);
+        std::fs::write(path, b"garbage").unwrap();
+        assert!(LanguageModel::load(path).is_err());
+        std::fs::remove_file(path).unwrap();
+        let path = std::env::temp_dir().join("cryptopals_language_model_corpus.txt");
+        let path = path.to_str().unwrap();
+        std::fs::write(path, &long).unwrap();
+        assert_eq!(LanguageModel::train_from_file(path).unwrap(), expected);
+        std::fs::remove_file(path).unwrap();
+        assert!(LanguageModel::train_from_file("./challenge_files/missing.txt").is_err());
+    }
+
+    #[test]
+    fn t_break_xor_with_trained_model() {
+        // log lines, which the English letter table knows nothing about
+        let log_line = |i: u32| {
+            format!(
+                "{{\"ts\":{},\"level\":\"{}\",\"code\":{},\"ok\":{}}}\n",
+                1700000000 + 37 * i,
+                ["info", "warn", "error"][i as usize % 3],
+                200 + (i * 7) % 300,
+                i.is_multiple_of(2)
+            )
+            .into_bytes()
+        };
+        let mut model = LanguageModel::new();
+        for i in 0..200 {
+            model.train(&log_line(i));
+        }
+        let model = LanguageModel::from_bytes(&model.to_bytes()).unwrap();
+        let plaintext: Vec<u8> = (1000..1012).flat_map(log_line).collect();
+        let ciphertext = xor_bytes(&plaintext, b"\x8f\x13\x5c\xe2");
+        // the columns are every fourth byte, so only unigrams mean anything
+        let unigrams = NgramScorer::from_model(&model, 1);
+        let (key, recovered) = break_repeating_key_xor_with(&ciphertext, vec![4], &unigrams);
+        assert_eq!(key, b"\x8f\x13\x5c\xe2");
+        assert_eq!(recovered, plaintext);
+        let chi_squared = ChiSquaredScorer::from_model(&model, false);
+        let (key, _) = break_repeating_key_xor_with(&ciphertext, vec![3, 4, 5], &chi_squared);
+        assert_eq!(key, b"\x8f\x13\x5c\xe2");
+        let (key, _) =
+            break_repeating_key_xor_with(&ciphertext, vec![4], &ChiSquaredScorer::english());
+        assert_ne!(key, b"\x8f\x13\x5c\xe2");
+    }
+
+    #[test]
+    fn t_rank_single_byte_xor() {
+        let input_bytes =
+            hex_to_bytes("1b37373331363f78151b7f2b783431333d78397828372d363c78373e783a393b3736");
+        let keys_plaintexts = brute_single_byte_xor_cipher(&input_bytes);
+        let ranking = rank_plaintexts(&keys_plaintexts, 5);
+        assert_eq!(ranking.candidates.len(), 5);
+        assert_eq!(
+            ranking.best().unwrap().plaintext,
+            b"Cooking MC's like a pound of bacon"
+        );
+        assert_eq!(ranking.best().unwrap().key, vec![0x58]);
+        assert!(
+            ranking
+                .candidates
+                .windows(2)
+                .all(|w| w[0].score >= w[1].score)
+        );
+        assert_eq!(
+            ranking.margin,
+            ranking.candidates[0].score - ranking.candidates[1].score
+        );
+        assert!(!ranking.is_ambiguous(0.1));
+        let chi_squared = rank_plaintexts_with(&keys_plaintexts, &ChiSquaredScorer::english(), 3);
+        assert_eq!(chi_squared.best().unwrap().key, vec![0x58]);
+        // E and e score the same in the letter table, so a lone letter is a coin toss
+        let keys_plaintexts = brute_single_byte_xor_cipher(&xor_bytes(b"E", &[0x21]));
+        let ranking = rank_plaintexts(&keys_plaintexts, 2);
+        assert_eq!(ranking.confidence, 0.0);
+        assert!(ranking.is_ambiguous(0.1));
+        let mut plaintexts: Vec<Vec<u8>> = ranking
+            .candidates
+            .iter()
+            .map(|c| c.plaintext.clone())
+            .collect();
+        plaintexts.sort();
+        assert_eq!(plaintexts, vec![b"E".to_vec(), b"e".to_vec()]);
+    }
+
+    #[test]
+    fn t_rank_single_character_xor() {
+        let lines_as_bytes = hex_file_to_bytes("./challenge_files/4.txt");
+        let ranking = rank_single_character_xor(&lines_as_bytes, 10);
+        assert_eq!(ranking.candidates.len(), 10);
+        assert_eq!(ranking.best().unwrap().line, 170);
+        assert_eq!(ranking.best().unwrap().key, vec![0x35]);
+        assert_eq!(
+            ranking.best().unwrap().plaintext,
+            b"Now that the party is jumping\n"
+        );
+        assert!(ranking.confidence > 0.05);
+        let ranking =
+            rank_single_character_xor_with(&lines_as_bytes, &ChiSquaredScorer::english(), 1);
+        assert_eq!(ranking.candidates.len(), 1);
+        assert_eq!(ranking.best().unwrap().line, 170);
+        // no lines and no pairs give empty rankings
+        assert!(rank_single_character_xor(&[], 10).candidates.is_empty());
+        assert_eq!(rank_plaintexts(&[], 5).best(), None);
+    }
+
+    #[test]
+    fn t_estimate_keysizes() {
+        let input_bytes = b64_file_to_bytes("./challenge_files/6.txt");
+        for method in [
+            KeysizeMethod::Hamming,
+            KeysizeMethod::IndexOfCoincidence,
+            KeysizeMethod::Autocorrelation,
+            KeysizeMethod::Kasiski,
+        ] {
+            let scores = estimate_keysizes(&input_bytes, method);
+            assert_eq!(scores.len(), MAX_KEYSIZE);
+            assert!(scores.windows(2).all(|w| w[0].1 >= w[1].1));
+            // the real keysize or its double comes first, and both are well clear of the rest
+            let top: Vec<usize> = scores[..2].iter().map(|&(keysize, _)| keysize).collect();
+            assert!(
+                top.contains(&29) && top.contains(&58),
+                "{method:?}: {top:?}"
+            );
+            assert!(scores[1].1 > 1.1 * scores[2].1);
+        }
+        // short inputs only consider keysizes they can say something about
+        let scores = estimate_keysizes(&input_bytes[..40], KeysizeMethod::Hamming);
+        assert_eq!(scores.len(), 10);
+        assert_eq!(
+            estimate_keysizes_up_to(&input_bytes[..9], KeysizeMethod::IndexOfCoincidence, 64).len(),
+            4
+        );
+        assert!(estimate_keysizes(b"abc", KeysizeMethod::Autocorrelation).len() == 1);
+        assert!(estimate_keysizes(b"", KeysizeMethod::Kasiski).is_empty());
+    }
+
+    #[test]
+    fn t_beam_break_repeating_key_xor() {
+        let corpus = lyrics_corpus();
+        let bigrams = NgramScorer::from_corpus(&corpus, 2);
+        let text = b"It was the best of times, it was the worst of times, it was the age of wisdom, it was the age of foolishness, it was the epoch of belief, it was the epoch of incredulity, it was the season of Light, it was the season of Darkness, it was the spring of hope, it was the winter of despair.";
+        let key = b"correct horse battery";
+        let ciphertext = xor_bytes(text, key);
+        // with only 13 or 14 bytes a column, the best byte for each column alone gets one wrong
+        let (column_key, _) = break_repeating_key_xor(&ciphertext, vec![21]);
+        assert_eq!(column_key, b"correct ~orse battery");
+        let params = BeamSearchParams::default();
+        let report = beam_break_repeating_key_xor(
+            &ciphertext,
+            &[20, 21, 22],
+            &LetterTableScorer,
+            &bigrams,
+            &params,
+        );
+        assert_eq!(report.keysize, 21);
+        assert_eq!(report.key, key);
+        assert_eq!(report.plaintext, text);
+        assert_eq!(
+            report.score,
+            text.windows(2).map(|pair| bigrams.score(pair)).sum::<f64>()
+        );
+        assert_eq!(report.column_confidence.len(), 21);
+        assert!(
+            report
+                .column_confidence
+                .iter()
+                .all(|c| (0.0..=1.0).contains(c))
+        );
+        // the wrong byte came from a column that was a tie on its own
+        assert_eq!(report.column_confidence[8], 0.0);
+        // twice the keysize finds the key twice over, one copy with a wrong byte
+        let ciphertext = xor_bytes(text, b"YELLOW SUBMARINE");
+        let report =
+            beam_break_repeating_key_xor(&ciphertext, &[32], &LetterTableScorer, &bigrams, &params);
+        assert_eq!(report.keysize, 16);
+        assert_eq!(report.key, b"YELLOW SUBMARINE");
+        assert_eq!(report.plaintext, text);
+    }
+
+    struct CountingScorer {
+        calls: std::cell::Cell<usize>,
+    }
+
+    impl Scorer for CountingScorer {
+        fn score(&self, bytes: &[u8]) -> f64 {
+            self.calls.set(self.calls.get() + 1);
+            LetterTableScorer.score(bytes)
+        }
+    }
+
+    #[test]
+    fn t_search_keyspace() {
+        let plaintext = b"Cooking MC's like a pound of bacon".to_vec();
+        // 0xfe and 0xff used to be left out
+        let keys_plaintexts = brute_single_byte_xor_cipher(&xor_bytes(&plaintext, &[0xff]));
+        assert_eq!(keys_plaintexts.len(), 256);
+        assert_eq!(highest_scoring_plaintext(&keys_plaintexts).0, vec![0xff]);
+        let input_bytes =
+            hex_to_bytes("1b37373331363f78151b7f2b783431333d78397828372d363c78373e783a393b3736");
+        assert_eq!(
+            search_keyspace(&input_bytes, &KeySpace::single_byte(), 5),
+            rank_plaintexts(&brute_single_byte_xor_cipher(&input_bytes), 5)
+        );
+        // keyspaces
+        let printable = KeySpace::printable(2);
+        assert_eq!(printable.alphabet.len(), 98);
+        assert_eq!(printable.size(), Some(98 * 98));
+        let keys: Vec<Vec<u8>> = printable.keys().collect();
+        assert_eq!(keys.len(), 98 * 98);
+        assert_eq!(keys[0], b"\t\t");
+        assert_eq!(keys[1], b"\t\n");
+        assert_eq!(keys[keys.len() - 1], b"~~");
+        let digits = KeySpace::new(b"9876543210", 3);
+        assert_eq!(digits.keys().nth(123), Some(b"123".to_vec()));
+        let letters = KeySpace::all_bytes(1).filtered(|b| b.is_ascii_lowercase());
+        assert_eq!(letters.keys().count(), 26);
+        assert_eq!(KeySpace::new(b"", 4).keys().count(), 0);
+        assert_eq!(KeySpace::all_bytes(9).size(), None);
+        // a two byte printable key
+        let ciphertext = xor_bytes(&plaintext, b"Hi");
+        let ranking = search_keyspace(&ciphertext, &printable, 3);
+        assert_eq!(ranking.candidates.len(), 3);
+        assert_eq!(ranking.best().unwrap().key, b"Hi");
+        assert_eq!(ranking.best().unwrap().plaintext, plaintext);
+        // an empty keyspace gives an empty ranking
+        let empty = search_keyspace(&ciphertext, &KeySpace::new(b"", 4), 3);
+        assert!(empty.candidates.is_empty());
+        assert_eq!(empty.best(), None);
+        assert!(empty.is_ambiguous(0.1));
+        // a threshold the real plaintext meets stops the search at its key
+        let scorer = CountingScorer {
+            calls: std::cell::Cell::new(0),
+        };
+        let threshold = LetterTableScorer.score(&plaintext);
+        let found =
+            find_key_with(&input_bytes, &KeySpace::single_byte(), &scorer, threshold).unwrap();
+        assert_eq!((found.key, found.plaintext), (vec![0x58], plaintext));
+        assert_eq!(scorer.calls.get(), 0x59);
+        scorer.calls.set(0);
+        assert_eq!(
+            find_key_with(
+                &input_bytes,
+                &KeySpace::single_byte(),
+                &scorer,
+                threshold + 1.0
+            ),
+            None
+        );
+        assert_eq!(scorer.calls.get(), 256);
+    }
+
+    #[test]
+    fn t_many_time_pad() {
+        let corpus = lyrics_corpus();
+        let lines: Vec<Vec<u8>> = corpus
+            .split(|&b| b == b'\n')
+            .filter(|line| line.len() > 10)
+            .map(|line| line.to_vec())
+            .collect();
+        let key = b"0123456789abcdef";
+        let ciphertexts: Vec<Vec<u8>> =
+            lines.iter().map(|line| aes_128_ctr(line, key, 0)).collect();
+        let longest = lines.iter().map(|line| line.len()).max().unwrap();
+        let keystream = aes_128_ctr(&vec![0; longest], key, 0);
+        // crib dragging two lines finds " the " where the other has "ile t"
+        let matches = crib_drag(&ciphertexts[..2], b" the ", 3);
+        assert_eq!(
+            (matches[0].first, matches[0].second, matches[0].offset),
+            (0, 1, 24)
+        );
+        assert_eq!(matches[0].revealed, b"ile t");
+        assert_eq!(&lines[0][24..29], b" the ");
+        assert_eq!(&lines[1][24..29], b"ile t");
+        // most flagged bytes are spaces, and most spaces are flagged
+        let spaces = detect_spaces(&ciphertexts, 0.7);
+        let flagged: Vec<u8> = lines
+            .iter()
+            .zip(&spaces)
+            .flat_map(|(line, flags)| line.iter().zip(flags).filter(|&(_, &f)| f).map(|(&b, _)| b))
+            .collect();
+        let real_spaces = lines.iter().flatten().filter(|&&b| b == b' ').count();
+        let found = flagged.iter().filter(|&&b| b == b' ').count();
+        assert!(found as f64 > 0.85 * flagged.len() as f64);
+        assert!(found as f64 > 0.75 * real_spaces as f64);
+        // the column solver only goes wrong where lines start with capitals and where few lines
+        // are long enough to vote
+        let solved = solve_columns(&ciphertexts);
+        assert_eq!(solved.len(), longest);
+        assert_ne!(solved[0], keystream[0]);
+        assert_eq!(solved[2..50], keystream[2..50]);
+        // the editor
+        let mut pad = ManyTimePad::new(ciphertexts.clone());
+        assert_eq!(pad.plaintext(0, b'?'), vec![b'?'; lines[0].len()]);
+        pad.apply_spaces(0.7);
+        let known = pad.keystream.iter().flatten().count();
+        let right = pad
+            .keystream
+            .iter()
+            .zip(&keystream)
+            .filter(|&(k, r)| *k == Some(*r))
+            .count();
+        assert!(known > longest / 2 && right as f64 > 0.8 * known as f64);
+        pad.solve_with(&LetterTableScorer);
+        assert!(pad.keystream.iter().all(|k| k.is_some()));
+        assert_ne!(pad.plaintext(0, b'?'), lines[0]);
+        // one confirmed line is enough for every other line as far as it reaches
+        let index = lines.iter().position(|line| line.len() == longest).unwrap();
+        pad.guess(index, 0, &lines[index]);
+        assert_eq!(pad.plaintexts(b'?'), lines);
+        pad.forget(0, 3);
+        assert_eq!(&pad.plaintext(0, b'?')[..5], b"??? b");
+    }
+
+    #[test]
+    fn t_recover_key_from_known_plaintext() {
+        assert_eq!(keystream_period(b"abcabcab"), Some(3));
+        assert_eq!(keystream_period(b"aaaa"), Some(1));
+        assert_eq!(keystream_period(b"abcd"), None);
+        assert_eq!(keystream_period(b""), None);
+        let corpus = lyrics_corpus();
+        let known = b"Play that funky music";
+        let ciphertext = xor_bytes(&corpus, b"ICE");
+        let found = recover_key_from_known_plaintext(&ciphertext, known, 3);
+        let first = corpus
+            .windows(known.len())
+            .position(|w| w == known)
+            .unwrap();
+        assert_eq!(found[0].offset, first);
+        assert_eq!(found[0].key, b"ICE");
+        assert_eq!(found[0].plaintext, corpus);
+        // every placement of known that finds the same key is only counted once
+        assert!(
+            found
+                .iter()
+                .skip(1)
+                .all(|candidate| candidate.key != b"ICE")
+        );
+        // a PNG header is enough for keys up to 7 bytes, here with a text chunk after it
+        let png_magic = b"\x89PNG\r\n\x1a\n";
+        let png = [
+            &png_magic[..],
+            b"\x00\x00\x00\x1btEXtComment\x00XOR is not encryption",
+        ]
+        .concat();
+        let key = [0x13, 0x37, 0xc0, 0xde, 0x42];
+        let ciphertext = xor_bytes(&png, &key);
+        let found =
+            recover_key_from_known_plaintext_with(&ciphertext, png_magic, &PrintableScorer, 1);
+        assert_eq!(found[0].offset, 0);
+        assert_eq!(found[0].key, key);
+        assert_eq!(found[0].plaintext, png);
+        let found = recover_key_from_known_plaintext(&ciphertext, b"tEXtComment", 1);
+        assert_eq!(found[0].key, key);
+        assert_eq!(found[0].offset, 12);
+        assert!(recover_key_from_known_plaintext(&ciphertext[..4], png_magic, 1).is_empty());
+    }
+
+    fn pe_like_binary(rng: &mut StdRng) -> Vec<u8> {
+        // a PE header followed by runs of code-like random bytes, zero padding and strings
+        let mut binary = vec![0u8; 0x100];
+        binary[..2].copy_from_slice(b"MZ");
+        binary[0x3c..0x40].copy_from_slice(&0x80u32.to_le_bytes());
+        binary[0x80..0x84].copy_from_slice(b"PE\0\0");
+        for i in 0..24 {
+            match i % 3 {
+                0 => binary.extend((0..80).map(|_| rng.random::<u8>())),
+                1 => binary.extend(vec![0u8; 48]),
+                _ => binary.extend(b"GetProcAddress\0LoadLibraryA\0kernel32.dll\0"),
+            }
+        }
+        binary
+    }
+
+    #[test]
+    fn t_file_type_scorer() {
+        use flate2::write::GzEncoder;
+        use std::io::Write;
+        let mut gzip = GzEncoder::new(vec![], flate2::Compression::default());
+        gzip.write_all(b"hello hello hello").unwrap();
+        let gzip = gzip.finish().unwrap();
+        let mut rng = StdRng::seed_from_u64(49);
+        let pe = pe_like_binary(&mut rng);
+        let elf = [&b"\x7fELF\x02\x01\x01"[..], &[0; 57]].concat();
+        let zip = [
+            &b"PK\x03\x04\x14\x00\x00\x00\x08\x00"[..],
+            &[0; 20],
+            b"PK\x05\x06",
+            &[0; 18],
+        ]
+        .concat();
+        let png = [
+            &b"\x89PNG\r\n\x1a\n"[..],
+            b"\x00\x00\x00\x0dIHDR",
+            &[0, 0, 0, 1, 0, 0, 0, 1, 8, 2, 0, 0, 0],
+            &[0x90, 0x77, 0x53, 0xde],
+            b"\x00\x00\x00\x00IEND\xae\x42\x60\x82",
+        ]
+        .concat();
+        let pdf = b"%PDF-1.7\n1 0 obj\n<< /Type /Catalog >>\nendobj\n%%EOF\n".to_vec();
+        let samples = [
+            (&pe, FileType::Pe, 3.0),
+            (&elf, FileType::Elf, 4.0),
+            (&zip, FileType::Zip, 2.0),
+            (&png, FileType::Png, 3.0),
+            (&gzip, FileType::Gzip, 3.0),
+            (&pdf, FileType::Pdf, 3.0),
+        ];
+        for (bytes, file_type, points) in samples {
+            assert_eq!(detect_file_type(bytes), Some(file_type));
+            let score = FileTypeScorer.score(bytes);
+            assert!(
+                score >= points && score < points + 1.0,
+                "{file_type:?}: {score}"
+            );
+        }
+        assert_eq!(detect_file_type(b"MZ"), Some(FileType::Pe));
+        assert_eq!(detect_file_type(b"just some text"), None);
+        assert_eq!(shannon_entropy(&[7; 100]), 0.0);
+        assert_eq!(shannon_entropy(&(0..=255).collect::<Vec<u8>>()), 8.0);
+        assert_eq!(FileTypeScorer.score(b""), 0.0);
+        // the letter table reads an xored binary as the key that makes the most letters
+        let ciphertext = xor_bytes(&pe, &[0x5a]);
+        let keys_plaintexts = brute_single_byte_xor_cipher(&ciphertext);
+        assert_ne!(highest_scoring_plaintext(&keys_plaintexts).0, vec![0x5a]);
+        let best = highest_scoring_plaintext_with(&keys_plaintexts, &FileTypeScorer);
+        assert_eq!((best.0, best.1), (vec![0x5a], pe.clone()));
+        let key = b"\xde\xad\xbe\xef\x01";
+        let ciphertext = xor_bytes(&pe, key);
+        assert_ne!(
+            break_repeating_key_xor(&ciphertext, vec![3, 4, 5, 6, 7]).0,
+            key
+        );
+        let (found, plaintext) =
+            break_repeating_key_xor_with(&ciphertext, vec![3, 4, 5, 6, 7], &FileTypeScorer);
+        assert_eq!(found, key);
+        assert_eq!(plaintext, pe);
+    }
+
+    #[test]
+    fn t_xor_variants() {
+        let plaintext = b"Now that the party is jumping".to_vec();
+        // incrementing key
+        let ciphertext = incrementing_xor(&plaintext, 0x41, 3);
+        assert_eq!(ciphertext[..2], [b'N' ^ 0x41, b'o' ^ 0x44]);
+        assert_eq!(incrementing_xor(&ciphertext, 0x41, 3), plaintext);
+        let ranking = break_incrementing_xor(&ciphertext, 3);
+        assert_eq!(ranking.best().unwrap().key, vec![0x41, 3]);
+        assert_eq!(ranking.best().unwrap().plaintext, plaintext);
+        // rolling xor, where ciphertext feedback only hides the first byte behind the iv
+        for feedback in [Feedback::Ciphertext, Feedback::Plaintext] {
+            let ciphertext = rolling_xor_encrypt(&plaintext, 0x5c, 0x17, feedback);
+            assert_eq!(ciphertext[0], b'N' ^ 0x5c ^ 0x17);
+            assert_eq!(
+                rolling_xor_decrypt(&ciphertext, 0x5c, 0x17, feedback),
+                plaintext
+            );
+            let best = break_rolling_xor(&ciphertext, feedback, 1)
+                .best()
+                .unwrap()
+                .clone();
+            assert_eq!(best.key[0], 0x5c);
+            assert_eq!(best.plaintext[1..], plaintext[1..]);
+        }
+        let ciphertext = rolling_xor_encrypt(&plaintext, 0x5c, 0x17, Feedback::Plaintext);
+        let best = break_rolling_xor(&ciphertext, Feedback::Plaintext, 1)
+            .best()
+            .unwrap()
+            .clone();
+        assert_eq!(best.key, vec![0x5c, 0x17]);
+        assert_eq!(best.plaintext, plaintext);
+        // xor, add and rotate
+        let cipher = XorAddRol {
+            xor: 0x3a,
+            add: 0x91,
+            rotate: 3,
+        };
+        assert_eq!(
+            cipher.encrypt_byte(b'N'),
+            ((b'N' ^ 0x3a).wrapping_add(0x91)).rotate_left(3)
+        );
+        let ciphertext = cipher.encrypt(&plaintext);
+        assert_eq!(cipher.decrypt(&ciphertext), plaintext);
+        let best = break_xor_add_rol(&ciphertext, 1).best().unwrap().clone();
+        assert_eq!(best.plaintext, plaintext);
+        let found = XorAddRol {
+            xor: best.key[0],
+            add: best.key[1],
+            rotate: best.key[2] as u32,
+        };
+        assert_eq!(found.encrypt(&plaintext), ciphertext);
+        // an LCG keystream seeded from a timestamp
+        let lcg = Lcg::msvc();
+        assert_eq!(lcg.step_back(lcg.step(0xdeadbeef)), 0xdeadbeef);
+        // the first values of rand() after srand(1)
+        let outputs: Vec<u32> = std::iter::successors(Some(lcg.step(1)), |&s| Some(lcg.step(s)))
+            .take(3)
+            .map(|s| (s >> 16) & 0x7fff)
+            .collect();
+        assert_eq!(outputs, vec![41, 18467, 6334]);
+        assert_eq!(lcg.keystream(1, 3), vec![41, 18467u32 as u8, 6334u32 as u8]);
+        let seed = 1_700_000_123;
+        let ciphertext = lcg_xor(&plaintext, &lcg, seed);
+        assert_eq!(lcg_xor(&ciphertext, &lcg, seed), plaintext);
+        let ranking = break_lcg_xor(&ciphertext, &lcg, 1_700_000_000..1_700_001_000, 2);
+        assert_eq!(ranking.best().unwrap().key, seed.to_be_bytes());
+        assert_eq!(ranking.best().unwrap().plaintext, plaintext);
+        assert_eq!(break_lcg_xor(&ciphertext, &lcg, 5..5, 1).best(), None);
+        // only the low 24 bits of the seed matter, and four known bytes pin them down
+        for lcg in [Lcg::msvc(), Lcg::ansi_c()] {
+            let ciphertext = lcg_xor(&plaintext, &lcg, seed);
+            let recovered = recover_lcg_seed(&ciphertext, b"Now ", &lcg).unwrap();
+            assert_eq!(recovered & 0xffffff, seed & 0xffffff);
+            assert_eq!(lcg_xor(&ciphertext, &lcg, recovered), plaintext);
+        }
+        assert_eq!(recover_lcg_seed(&ciphertext, b"", &lcg), None);
+    }
 }
@@ -0,0 +1,655 @@
+use crate::scoring::{LetterTableScorer, Scorer, is_printable};
+use crate::{brute_single_byte_xor_cipher, xor_bytes};
+use std::collections::HashMap;
+
+// analysis of XOR ciphers that goes beyond picking a single best guess
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct XorCandidate {
+    // which input the candidate decrypts, always 0 for a single ciphertext
+    pub line: usize,
+    pub key: Vec<u8>,
+    pub plaintext: Vec<u8>,
+    pub score: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct XorRanking {
+    // the best candidates, highest score first, and empty if there was nothing to rank
+    pub candidates: Vec<XorCandidate>,
+    // how far the best score is ahead of the second best
+    pub margin: f64,
+    // the margin as a fraction of the spread between the best and worst of all candidates
+    // scored, so 0 is a tie for first place and 1 means the runner up is as bad as the worst.
+    // rescaling the scores leaves it unchanged, but thresholds still depend on the scorer
+    pub confidence: f64,
+}
+
+impl XorRanking {
+    fn new(mut scored: Vec<XorCandidate>, n: usize) -> Self {
+        // stable, so ties keep their input order
+        scored.sort_by(|a, b| b.score.total_cmp(&a.score));
+        let worst = scored.last().map_or(0.0, |candidate| candidate.score);
+        scored.truncate(n.max(2));
+        XorRanking::from_top(scored, worst, n)
+    }
+
+    fn from_top(mut top: Vec<XorCandidate>, worst: f64, n: usize) -> Self {
+        // top holds at least the best two candidates of all those scored, highest first
+        assert!(n > 0, "Need at least one candidate.");
+        let (margin, confidence) = match (top.first(), top.get(1)) {
+            (Some(best), Some(second)) if best.score > worst => (
+                best.score - second.score,
+                (best.score - second.score) / (best.score - worst),
+            ),
+            (Some(_), Some(_)) => (0.0, 0.0),
+            // a lone candidate has no competition
+            (Some(_), None) => (f64::INFINITY, 1.0),
+            // an empty ranking is as ambiguous as it gets
+            (None, _) => (0.0, 0.0),
+        };
+        top.truncate(n);
+        XorRanking {
+            candidates: top,
+            margin,
+            confidence,
+        }
+    }
+
+    pub fn best(&self) -> Option<&XorCandidate> {
+        self.candidates.first()
+    }
+
+    pub fn is_ambiguous(&self, min_confidence: f64) -> bool {
+        self.confidence < min_confidence
+    }
+}
+
+pub fn rank_plaintexts(keys_plaintexts: &[(Vec<u8>, Vec<u8>)], n: usize) -> XorRanking {
+    rank_plaintexts_with(keys_plaintexts, &LetterTableScorer, n)
+}
+
+pub fn rank_plaintexts_with<S: Scorer + ?Sized>(
+    keys_plaintexts: &[(Vec<u8>, Vec<u8>)],
+    scorer: &S,
+    n: usize,
+) -> XorRanking {
+    // the top n of the (key, plaintext) pairs from brute_single_byte_xor_cipher and the like
+    let scored = keys_plaintexts
+        .iter()
+        .map(|(key, plaintext)| XorCandidate {
+            line: 0,
+            key: key.clone(),
+            plaintext: plaintext.clone(),
+            score: scorer.score(plaintext),
+        })
+        .collect();
+    XorRanking::new(scored, n)
+}
+
+pub fn rank_single_character_xor(input_bytes: &[Vec<u8>], n: usize) -> XorRanking {
+    rank_single_character_xor_with(input_bytes, &LetterTableScorer, n)
+}
+
+pub fn rank_single_character_xor_with<S: Scorer + ?Sized>(
+    input_bytes: &[Vec<u8>],
+    scorer: &S,
+    n: usize,
+) -> XorRanking {
+    // the top n decryptions over every key and every line, for finding the one line of many
+    // that was encrypted with single-byte XOR
+    let scored = input_bytes
+        .iter()
+        .enumerate()
+        .flat_map(|(line, ciphertext)| {
+            brute_single_byte_xor_cipher(ciphertext)
+                .into_iter()
+                .map(move |(key, plaintext)| (line, key, plaintext))
+        })
+        .map(|(line, key, plaintext)| XorCandidate {
+            line,
+            score: scorer.score(&plaintext),
+            key,
+            plaintext,
+        })
+        .collect();
+    XorRanking::new(scored, n)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeysizeMethod {
+    // mean normalised Hamming distance between every pair of keysize blocks
+    Hamming,
+    // mean index of coincidence of the columns of bytes sharing a key byte
+    IndexOfCoincidence,
+    // how often a byte equals the one keysize places later
+    Autocorrelation,
+    // how many of the gaps between repeated trigrams keysize divides
+    Kasiski,
+}
+
+// estimate_keysizes never looks past this many bytes of key
+pub const MAX_KEYSIZE: usize = 64;
+
+pub fn estimate_keysizes(input_bytes: &[u8], method: KeysizeMethod) -> Vec<(usize, f64)> {
+    // keysizes up to MAX_KEYSIZE, or fewer for short inputs so that every column has at least
+    // four bytes in it
+    let max_keysize = (input_bytes.len() / 4).clamp(1, MAX_KEYSIZE);
+    estimate_keysizes_up_to(input_bytes, method, max_keysize)
+}
+
+pub fn estimate_keysizes_up_to(
+    input_bytes: &[u8],
+    method: KeysizeMethod,
+    max_keysize: usize,
+) -> Vec<(usize, f64)> {
+    // (keysize, score) for keysizes from 1 to max_keysize, capped at half the input, best first.
+    // every score is how many times better than random bytes the keysize does, so about 1 means
+    // nothing and higher is better. multiples of the real keysize tend to do as well as it does
+    let max_keysize = max_keysize.min(input_bytes.len() / 2);
+    let mut scores: Vec<(usize, f64)> = (1..=max_keysize)
+        .map(|keysize| {
+            let score = match method {
+                KeysizeMethod::Hamming => hamming_keysize_score(input_bytes, keysize),
+                KeysizeMethod::IndexOfCoincidence => {
+                    index_of_coincidence_keysize_score(input_bytes, keysize)
+                }
+                KeysizeMethod::Autocorrelation => {
+                    autocorrelation_keysize_score(input_bytes, keysize)
+                }
+                KeysizeMethod::Kasiski => kasiski_keysize_score(input_bytes, keysize),
+            };
+            (keysize, score)
+        })
+        .collect();
+    // stable, so ties go to the smaller keysize
+    scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scores
+}
+
+fn hamming_keysize_score(input_bytes: &[u8], keysize: usize) -> f64 {
+    // across all pairs of blocks, each bit position contributes ones * zeros differing pairs, so
+    // the mean over every pair takes a single pass. random bytes differ in 4 bits of 8
+    let blocks = input_bytes.len() / keysize;
+    let mut ones = vec![0u64; keysize * 8];
+    for block in input_bytes.chunks_exact(keysize) {
+        for (i, byte) in block.iter().enumerate() {
+            for bit in 0..8 {
+                ones[i * 8 + bit] += ((byte >> bit) & 1) as u64;
+            }
+        }
+    }
+    let differing: u64 = ones.iter().map(|&o| o * (blocks as u64 - o)).sum();
+    let pairs = (blocks * (blocks - 1) / 2) as f64;
+    let bits_per_byte = differing as f64 / (pairs * keysize as f64);
+    4.0 / bits_per_byte
+}
+
+fn index_of_coincidence(bytes: &[u8]) -> f64 {
+    // the chance that two bytes picked from different positions are equal
+    let mut counts = [0u64; 256];
+    for &b in bytes {
+        counts[b as usize] += 1;
+    }
+    let n = bytes.len() as u64;
+    let pairs: u64 = counts.iter().map(|&c| c * c.saturating_sub(1)).sum();
+    pairs as f64 / (n * (n - 1)) as f64
+}
+
+fn index_of_coincidence_keysize_score(input_bytes: &[u8], keysize: usize) -> f64 {
+    // a column under one key byte keeps the plaintext's uneven byte frequencies, while a
+    // column mixing key bytes flattens them towards 1/256
+    let mean = columns(input_bytes, keysize)
+        .iter()
+        .map(|column| index_of_coincidence(column))
+        .sum::<f64>()
+        / keysize as f64;
+    mean * 256.0
+}
+
+fn autocorrelation_keysize_score(input_bytes: &[u8], keysize: usize) -> f64 {
+    let matches = input_bytes
+        .iter()
+        .zip(&input_bytes[keysize..])
+        .filter(|(a, b)| a == b)
+        .count();
+    matches as f64 / (input_bytes.len() - keysize) as f64 * 256.0
+}
+
+fn kasiski_keysize_score(input_bytes: &[u8], keysize: usize) -> f64 {
+    // a keysize divides the gap between repeats of the same plaintext under the same key, and
+    // by chance only one gap in keysize
+    let mut last_seen: HashMap<&[u8], usize> = HashMap::new();
+    let mut gaps = vec![];
+    for (i, trigram) in input_bytes.windows(3).enumerate() {
+        if let Some(previous) = last_seen.insert(trigram, i) {
+            gaps.push(i - previous);
+        }
+    }
+    if gaps.is_empty() {
+        return 0.0;
+    }
+    let divisible = gaps.iter().filter(|&&gap| gap % keysize == 0).count();
+    divisible as f64 / gaps.len() as f64 * keysize as f64
+}
+
+fn columns(input_bytes: &[u8], keysize: usize) -> Vec<Vec<u8>> {
+    // the bytes under each byte of a repeating key
+    (0..keysize)
+        .map(|i| {
+            input_bytes
+                .iter()
+                .skip(i)
+                .step_by(keysize)
+                .copied()
+                .collect()
+        })
+        .collect()
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BeamSearchParams {
+    // how many of the best bytes for each key column the search may choose from
+    pub column_candidates: usize,
+    // how many partial keys survive each column
+    pub beam_width: usize,
+}
+
+impl Default for BeamSearchParams {
+    fn default() -> Self {
+        BeamSearchParams {
+            column_candidates: 8,
+            beam_width: 32,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RepeatingKeyXorReport {
+    pub keysize: usize,
+    pub key: Vec<u8>,
+    pub plaintext: Vec<u8>,
+    // the bigram score of the whole plaintext
+    pub score: f64,
+    // the XorRanking confidence of each key byte judged on its column alone, so a low value
+    // marks a byte the bigrams are more likely to have chosen
+    pub column_confidence: Vec<f64>,
+}
+
+fn bigram_score<T: Scorer + ?Sized>(plaintext: &[u8], bigram_scorer: &T) -> f64 {
+    plaintext
+        .windows(2)
+        .map(|pair| bigram_scorer.score(pair))
+        .sum()
+}
+
+fn fold_key<T: Scorer + ?Sized>(input_bytes: &[u8], key: &[u8], bigram_scorer: &T) -> Vec<u8> {
+    // a multiple of the real keysize finds the real key repeated, though with short columns a
+    // copy may have a byte wrong. a key where at least three quarters of the bytes match the byte
+    // a period earlier is folded to that period, taking whichever copy of each byte gives the
+    // best bigram score
+    let periods = (1..key.len()).filter(|&period| key.len().is_multiple_of(period));
+    for period in periods {
+        let repeats = key.len() - period;
+        let matching = (period..key.len())
+            .filter(|&i| key[i] == key[i - period])
+            .count();
+        if 4 * matching < 3 * repeats {
+            continue;
+        }
+        let mut folded = key[..period].to_vec();
+        for i in 0..period {
+            let mut choices: Vec<u8> = key.iter().skip(i).step_by(period).copied().collect();
+            choices.sort();
+            choices.dedup();
+            let scored: Vec<(u8, f64)> = choices
+                .into_iter()
+                .map(|byte| {
+                    folded[i] = byte;
+                    (
+                        byte,
+                        bigram_score(&xor_bytes(input_bytes, &folded), bigram_scorer),
+                    )
+                })
+                .collect();
+            folded[i] = scored.iter().max_by(|a, b| a.1.total_cmp(&b.1)).unwrap().0;
+        }
+        return folded;
+    }
+    key.to_vec()
+}
+
+pub fn beam_break_repeating_key_xor<S, T>(
+    input_bytes: &[u8],
+    keysizes: &[usize],
+    column_scorer: &S,
+    bigram_scorer: &T,
+    params: &BeamSearchParams,
+) -> RepeatingKeyXorReport
+where
+    S: Scorer + ?Sized,
+    T: Scorer + ?Sized,
+{
+    // column_scorer ranks the bytes of each key column on their own, then a beam search builds
+    // keys a column at a time, scoring each with bigram_scorer on the pairs of bytes that cross
+    // from one column into the next. that catches a key byte whose column looked best in
+    // isolation but joins badly onto its neighbours. bigram_scorer is only ever given two bytes,
+    // such as an NgramScorer with n = 2
+    assert!(
+        params.column_candidates > 0 && params.beam_width > 0,
+        "Need at least one candidate and one beam."
+    );
+    let keysizes: Vec<usize> = keysizes
+        .iter()
+        .copied()
+        .filter(|&keysize| keysize > 0 && keysize <= input_bytes.len())
+        .collect();
+    assert!(!keysizes.is_empty(), "No keysize fits the input.");
+    let best = keysizes
+        .iter()
+        .map(|&keysize| {
+            let key = beam_search_key(input_bytes, keysize, column_scorer, bigram_scorer, params);
+            let plaintext = xor_bytes(input_bytes, &key);
+            (bigram_score(&plaintext, bigram_scorer), key, plaintext)
+        })
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .unwrap();
+    let (mut score, mut key, mut plaintext) = best;
+    let folded = fold_key(input_bytes, &key, bigram_scorer);
+    if folded.len() < key.len() {
+        key = folded;
+        plaintext = xor_bytes(input_bytes, &key);
+        score = bigram_score(&plaintext, bigram_scorer);
+    }
+    let column_confidence = columns(input_bytes, key.len())
+        .iter()
+        .map(|column| {
+            rank_plaintexts_with(&brute_single_byte_xor_cipher(column), column_scorer, 1).confidence
+        })
+        .collect();
+    RepeatingKeyXorReport {
+        keysize: key.len(),
+        key,
+        plaintext,
+        score,
+        column_confidence,
+    }
+}
+
+fn beam_search_key<S, T>(
+    input_bytes: &[u8],
+    keysize: usize,
+    column_scorer: &S,
+    bigram_scorer: &T,
+    params: &BeamSearchParams,
+) -> Vec<u8>
+where
+    S: Scorer + ?Sized,
+    T: Scorer + ?Sized,
+{
+    let columns = columns(input_bytes, keysize);
+    let candidates: Vec<Vec<u8>> = columns
+        .iter()
+        .map(|column| {
+            rank_plaintexts_with(
+                &brute_single_byte_xor_cipher(column),
+                column_scorer,
+                params.column_candidates,
+            )
+            .candidates
+            .into_iter()
+            .map(|candidate| candidate.key[0])
+            .collect()
+        })
+        .collect();
+    // each beam is a key for the first few columns and the score of the pairs within them
+    let mut beams: Vec<(Vec<u8>, f64)> = candidates[0].iter().map(|&b| (vec![b], 0.0)).collect();
+    for (i, column_candidates) in candidates.iter().enumerate().skip(1) {
+        let (previous, current) = (&columns[i - 1], &columns[i]);
+        let mut extended: Vec<(Vec<u8>, f64)> = beams
+            .iter()
+            .flat_map(|(key, score)| {
+                column_candidates.iter().map(move |&b| {
+                    let pairs: f64 = previous
+                        .iter()
+                        .zip(current)
+                        .map(|(left, right)| bigram_scorer.score(&[left ^ key[i - 1], right ^ b]))
+                        .sum();
+                    ([key.as_slice(), &[b]].concat(), score + pairs)
+                })
+            })
+            .collect();
+        extended.sort_by(|a, b| b.1.total_cmp(&a.1));
+        extended.truncate(params.beam_width);
+        beams = extended;
+    }
+    // the pairs that wrap from the last column to the first are only known once keys are whole
+    beams
+        .into_iter()
+        .map(|(key, _)| {
+            let score = bigram_score(&xor_bytes(input_bytes, &key), bigram_scorer);
+            (key, score)
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .unwrap()
+        .0
+}
+
+// every key of a fixed length whose bytes all come from an alphabet, in lexicographic order
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeySpace {
+    pub alphabet: Vec<u8>,
+    pub key_len: usize,
+}
+
+impl KeySpace {
+    pub fn new(alphabet: &[u8], key_len: usize) -> Self {
+        // repeated bytes in alphabet are only tried once
+        assert!(key_len > 0, "Keys must be at least one byte.");
+        let mut alphabet = alphabet.to_vec();
+        alphabet.sort();
+        alphabet.dedup();
+        KeySpace { alphabet, key_len }
+    }
+
+    pub fn all_bytes(key_len: usize) -> Self {
+        KeySpace::new(&(0..=255).collect::<Vec<u8>>(), key_len)
+    }
+
+    pub fn single_byte() -> Self {
+        KeySpace::all_bytes(1)
+    }
+
+    pub fn printable(key_len: usize) -> Self {
+        // printable ASCII and common whitespace, for keys that were typed in
+        KeySpace::all_bytes(key_len).filtered(is_printable)
+    }
+
+    pub fn filtered<F: Fn(u8) -> bool>(&self, keep: F) -> Self {
+        KeySpace {
+            alphabet: self.alphabet.iter().copied().filter(|&b| keep(b)).collect(),
+            key_len: self.key_len,
+        }
+    }
+
+    pub fn size(&self) -> Option<u64> {
+        // None if there are more keys than fit in a u64
+        (self.alphabet.len() as u64).checked_pow(self.key_len.try_into().ok()?)
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = Vec<u8>> + '_ {
+        // an odometer over indices into the alphabet, with the last byte turning fastest
+        let mut indices = (!self.alphabet.is_empty()).then(|| vec![0; self.key_len]);
+        std::iter::from_fn(move || {
+            let current = indices.as_mut()?;
+            let key = current.iter().map(|&i| self.alphabet[i]).collect();
+            match current.iter().rposition(|&i| i + 1 < self.alphabet.len()) {
+                Some(position) => {
+                    current[position] += 1;
+                    current[position + 1..].fill(0);
+                }
+                None => indices = None,
+            }
+            Some(key)
+        })
+    }
+}
+
+pub fn search_keyspace(ciphertext: &[u8], keyspace: &KeySpace, n: usize) -> XorRanking {
+    search_keyspace_with(ciphertext, keyspace, &LetterTableScorer, n)
+}
+
+pub fn search_keyspace_with<S: Scorer + ?Sized>(
+    ciphertext: &[u8],
+    keyspace: &KeySpace,
+    scorer: &S,
+    n: usize,
+) -> XorRanking {
+    // the top n keys by the score of the ciphertext xored with them
+    rank_keys_with(
+        keyspace.keys(),
+        ciphertext.len(),
+        |key, plaintext| xor_into(ciphertext, key, plaintext),
+        scorer,
+        n,
+    )
+}
+
+pub(crate) fn rank_keys_with<I, F, S>(
+    keys: I,
+    len: usize,
+    mut decrypt_into: F,
+    scorer: &S,
+    n: usize,
+) -> XorRanking
+where
+    I: IntoIterator<Item = Vec<u8>>,
+    F: FnMut(&[u8], &mut [u8]),
+    S: Scorer + ?Sized,
+{
+    // the top n keys by the score of the len bytes decrypt_into writes for them. every key is
+    // decrypted into the same buffer, and only the plaintexts of the best few are ever kept
+    assert!(n > 0, "Need at least one candidate.");
+    let keep = n.max(2);
+    let mut top: Vec<XorCandidate> = Vec::with_capacity(keep + 1);
+    let mut worst = f64::INFINITY;
+    let mut plaintext = vec![0u8; len];
+    for key in keys {
+        decrypt_into(&key, &mut plaintext);
+        let score = scorer.score(&plaintext);
+        worst = worst.min(score);
+        if top.len() == keep && score <= top[keep - 1].score {
+            continue;
+        }
+        // after any equal scores, so ties keep the order of the keys
+        let position = top.partition_point(|candidate| candidate.score >= score);
+        top.insert(
+            position,
+            XorCandidate {
+                line: 0,
+                key,
+                plaintext: plaintext.clone(),
+                score,
+            },
+        );
+        top.truncate(keep);
+    }
+    XorRanking::from_top(top, worst, n)
+}
+
+pub fn find_key_with<S: Scorer + ?Sized>(
+    ciphertext: &[u8],
+    keyspace: &KeySpace,
+    scorer: &S,
+    threshold: f64,
+) -> Option<XorCandidate> {
+    // the first key in the keyspace whose plaintext scores at least threshold, without trying
+    // the rest. None if no key does
+    let mut plaintext = vec![0u8; ciphertext.len()];
+    for key in keyspace.keys() {
+        xor_into(ciphertext, &key, &mut plaintext);
+        let score = scorer.score(&plaintext);
+        if score >= threshold {
+            return Some(XorCandidate {
+                line: 0,
+                key,
+                plaintext,
+                score,
+            });
+        }
+    }
+    None
+}
+
+fn xor_into(input: &[u8], key: &[u8], out: &mut [u8]) {
+    for (i, (o, byte)) in out.iter_mut().zip(input).enumerate() {
+        *o = byte ^ key[i % key.len()];
+    }
+}
+
+pub fn keystream_period(keystream: &[u8]) -> Option<usize> {
+    // the smallest p shorter than keystream with keystream[i] = keystream[i - p] throughout,
+    // which is the length of a repeating key it could come from. None if it never repeats
+    (1..keystream.len()).find(|&period| {
+        keystream[period..]
+            .iter()
+            .zip(keystream)
+            .all(|(a, b)| a == b)
+    })
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct KnownPlaintextKey {
+    // where the known plaintext was placed to find the key
+    pub offset: usize,
+    pub key: Vec<u8>,
+    pub plaintext: Vec<u8>,
+    pub score: f64,
+}
+
+pub fn recover_key_from_known_plaintext(
+    ciphertext: &[u8],
+    known: &[u8],
+    n: usize,
+) -> Vec<KnownPlaintextKey> {
+    recover_key_from_known_plaintext_with(ciphertext, known, &LetterTableScorer, n)
+}
+
+pub fn recover_key_from_known_plaintext_with<S: Scorer + ?Sized>(
+    ciphertext: &[u8],
+    known: &[u8],
+    scorer: &S,
+    n: usize,
+) -> Vec<KnownPlaintextKey> {
+    // known is some plaintext at an unknown offset, like a file header. at each offset, known
+    // xored with the ciphertext gives a stretch of keystream, and where that repeats its period
+    // is a candidate repeating key, rotated to line up with the start of the ciphertext. the
+    // best n distinct keys by the score of the whole decryption, so only keys shorter than known
+    // are found, and a longer known turns up fewer false ones
+    let mut candidates: Vec<KnownPlaintextKey> = vec![];
+    if known.len() > ciphertext.len() {
+        return candidates;
+    }
+    for offset in 0..=ciphertext.len() - known.len() {
+        let keystream = xor_bytes(&ciphertext[offset..offset + known.len()], known);
+        let Some(period) = keystream_period(&keystream) else {
+            continue;
+        };
+        let mut key = keystream[..period].to_vec();
+        key.rotate_right(offset % period);
+        if candidates.iter().any(|candidate| candidate.key == key) {
+            continue;
+        }
+        let plaintext = xor_bytes(ciphertext, &key);
+        candidates.push(KnownPlaintextKey {
+            offset,
+            score: scorer.score(&plaintext),
+            key,
+            plaintext,
+        });
+    }
+    // stable, so ties go to the earlier offset
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+    candidates.truncate(n);
+    candidates
+}
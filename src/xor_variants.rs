@@ -0,0 +1,306 @@
+use crate::scoring::{LetterTableScorer, Scorer};
+use crate::xor::{XorRanking, rank_keys_with};
+
+// the byte-at-a-time XOR schemes that malware and CTFs use instead of a plain repeating key,
+// with a brute force for each that ranks keys by the score of their plaintext. the key of each
+// XorCandidate is the cipher's parameters packed into bytes, as each break function says
+
+pub fn incrementing_xor(input_bytes: &[u8], key: u8, step: u8) -> Vec<u8> {
+    // byte i is xored with key + i * step, wrapping, so encrypting and decrypting are the same
+    let mut out = vec![0u8; input_bytes.len()];
+    incrementing_xor_into(input_bytes, key, step, &mut out);
+    out
+}
+
+fn incrementing_xor_into(input_bytes: &[u8], key: u8, step: u8, out: &mut [u8]) {
+    let mut k = key;
+    for (o, b) in out.iter_mut().zip(input_bytes) {
+        *o = b ^ k;
+        k = k.wrapping_add(step);
+    }
+}
+
+pub fn break_incrementing_xor(ciphertext: &[u8], n: usize) -> XorRanking {
+    break_incrementing_xor_with(ciphertext, &LetterTableScorer, n)
+}
+
+pub fn break_incrementing_xor_with<S: Scorer + ?Sized>(
+    ciphertext: &[u8],
+    scorer: &S,
+    n: usize,
+) -> XorRanking {
+    // every key and step, with candidate keys [key, step]
+    let keys = (0..=255u8).flat_map(|key| (0..=255u8).map(move |step| vec![key, step]));
+    rank_keys_with(
+        keys,
+        ciphertext.len(),
+        |key, plaintext| incrementing_xor_into(ciphertext, key[0], key[1], plaintext),
+        scorer,
+        n,
+    )
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Feedback {
+    // each byte is also xored with the previous ciphertext byte
+    Ciphertext,
+    // each byte is also xored with the previous plaintext byte
+    Plaintext,
+}
+
+pub fn rolling_xor_encrypt(input_bytes: &[u8], key: u8, iv: u8, feedback: Feedback) -> Vec<u8> {
+    // c[i] = p[i] ^ key ^ previous, where previous is the byte before in the ciphertext or the
+    // plaintext, and iv before the first byte
+    let mut previous = iv;
+    input_bytes
+        .iter()
+        .map(|&p| {
+            let c = p ^ key ^ previous;
+            previous = match feedback {
+                Feedback::Ciphertext => c,
+                Feedback::Plaintext => p,
+            };
+            c
+        })
+        .collect()
+}
+
+pub fn rolling_xor_decrypt(input_bytes: &[u8], key: u8, iv: u8, feedback: Feedback) -> Vec<u8> {
+    let mut out = vec![0u8; input_bytes.len()];
+    rolling_xor_decrypt_into(input_bytes, key, iv, feedback, &mut out);
+    out
+}
+
+fn rolling_xor_decrypt_into(
+    input_bytes: &[u8],
+    key: u8,
+    iv: u8,
+    feedback: Feedback,
+    out: &mut [u8],
+) {
+    let mut previous = iv;
+    for (o, &c) in out.iter_mut().zip(input_bytes) {
+        *o = c ^ key ^ previous;
+        previous = match feedback {
+            Feedback::Ciphertext => c,
+            Feedback::Plaintext => *o,
+        };
+    }
+}
+
+pub fn break_rolling_xor(ciphertext: &[u8], feedback: Feedback, n: usize) -> XorRanking {
+    break_rolling_xor_with(ciphertext, feedback, &LetterTableScorer, n)
+}
+
+pub fn break_rolling_xor_with<S: Scorer + ?Sized>(
+    ciphertext: &[u8],
+    feedback: Feedback,
+    scorer: &S,
+    n: usize,
+) -> XorRanking {
+    // every key and iv, with candidate keys [key, iv]. with ciphertext feedback the iv only
+    // reaches the first byte, so keys that differ in it alone score almost the same
+    let keys = (0..=255u8).flat_map(|key| (0..=255u8).map(move |iv| vec![key, iv]));
+    rank_keys_with(
+        keys,
+        ciphertext.len(),
+        |key, plaintext| rolling_xor_decrypt_into(ciphertext, key[0], key[1], feedback, plaintext),
+        scorer,
+        n,
+    )
+}
+
+// each byte becomes rol((b ^ xor) + add, rotate), wrapping
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct XorAddRol {
+    pub xor: u8,
+    pub add: u8,
+    pub rotate: u32,
+}
+
+impl XorAddRol {
+    pub fn encrypt_byte(&self, b: u8) -> u8 {
+        (b ^ self.xor)
+            .wrapping_add(self.add)
+            .rotate_left(self.rotate)
+    }
+
+    pub fn decrypt_byte(&self, b: u8) -> u8 {
+        b.rotate_right(self.rotate).wrapping_sub(self.add) ^ self.xor
+    }
+
+    pub fn encrypt(&self, input_bytes: &[u8]) -> Vec<u8> {
+        input_bytes.iter().map(|&b| self.encrypt_byte(b)).collect()
+    }
+
+    pub fn decrypt(&self, input_bytes: &[u8]) -> Vec<u8> {
+        input_bytes.iter().map(|&b| self.decrypt_byte(b)).collect()
+    }
+}
+
+pub fn break_xor_add_rol(ciphertext: &[u8], n: usize) -> XorRanking {
+    break_xor_add_rol_with(ciphertext, &LetterTableScorer, n)
+}
+
+pub fn break_xor_add_rol_with<S: Scorer + ?Sized>(
+    ciphertext: &[u8],
+    scorer: &S,
+    n: usize,
+) -> XorRanking {
+    // every rotation, addend and xor, with candidate keys [xor, add, rotate]. some different
+    // keys are the same substitution, such as xor and add both 0x80 and both 0, and the first
+    // found is the one kept. half a million keys, so short ciphertexts are much quicker
+    let keys = (0..8u8).flat_map(|rotate| {
+        (0..=255u8).flat_map(move |add| (0..=255u8).map(move |xor| vec![xor, add, rotate]))
+    });
+    rank_keys_with(
+        keys,
+        ciphertext.len(),
+        |key, plaintext| {
+            let cipher = XorAddRol {
+                xor: key[0],
+                add: key[1],
+                rotate: key[2] as u32,
+            };
+            for (p, &c) in plaintext.iter_mut().zip(ciphertext) {
+                *p = cipher.decrypt_byte(c);
+            }
+        },
+        scorer,
+        n,
+    )
+}
+
+// a linear congruential generator mod 2^32, state = state * multiplier + increment, giving the
+// byte at bit shift of each new state as keystream
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Lcg {
+    pub multiplier: u32,
+    pub increment: u32,
+    pub shift: u32,
+}
+
+impl Lcg {
+    pub fn msvc() -> Self {
+        // rand() & 0xff from the Microsoft C runtime
+        Lcg {
+            multiplier: 214013,
+            increment: 2531011,
+            shift: 16,
+        }
+    }
+
+    pub fn ansi_c() -> Self {
+        // rand() & 0xff from the example in the C standard. its modulus is 2^31, but bits below
+        // 31 come out the same mod 2^32
+        Lcg {
+            multiplier: 1103515245,
+            increment: 12345,
+            shift: 16,
+        }
+    }
+
+    pub fn step(&self, state: u32) -> u32 {
+        state
+            .wrapping_mul(self.multiplier)
+            .wrapping_add(self.increment)
+    }
+
+    pub fn step_back(&self, state: u32) -> u32 {
+        // the multiplier must be odd, so that it has an inverse mod 2^32
+        assert!(self.multiplier % 2 == 1, "Multiplier must be odd.");
+        // Newton's iteration doubles the number of correct low bits each time, and any odd
+        // number is its own inverse mod 8
+        let mut inverse = self.multiplier;
+        for _ in 0..4 {
+            inverse =
+                inverse.wrapping_mul(2u32.wrapping_sub(self.multiplier.wrapping_mul(inverse)));
+        }
+        state.wrapping_sub(self.increment).wrapping_mul(inverse)
+    }
+
+    pub fn keystream(&self, seed: u32, len: usize) -> Vec<u8> {
+        let mut out = vec![0u8; len];
+        self.keystream_into(seed, &mut out);
+        out
+    }
+
+    fn keystream_into(&self, seed: u32, out: &mut [u8]) {
+        let mut state = seed;
+        for o in out.iter_mut() {
+            state = self.step(state);
+            *o = (state >> self.shift) as u8;
+        }
+    }
+}
+
+pub fn lcg_xor(input_bytes: &[u8], lcg: &Lcg, seed: u32) -> Vec<u8> {
+    // encrypting and decrypting are the same
+    let mut out = lcg.keystream(seed, input_bytes.len());
+    for (o, b) in out.iter_mut().zip(input_bytes) {
+        *o ^= b;
+    }
+    out
+}
+
+pub fn break_lcg_xor<I: IntoIterator<Item = u32>>(
+    ciphertext: &[u8],
+    lcg: &Lcg,
+    seeds: I,
+    n: usize,
+) -> XorRanking {
+    break_lcg_xor_with(ciphertext, lcg, seeds, &LetterTableScorer, n)
+}
+
+pub fn break_lcg_xor_with<I, S>(
+    ciphertext: &[u8],
+    lcg: &Lcg,
+    seeds: I,
+    scorer: &S,
+    n: usize,
+) -> XorRanking
+where
+    I: IntoIterator<Item = u32>,
+    S: Scorer + ?Sized,
+{
+    // every seed in seeds, such as the timestamps around when the file was written, with
+    // candidate keys the seed as 4 big endian bytes
+    rank_keys_with(
+        seeds.into_iter().map(|seed| seed.to_be_bytes().to_vec()),
+        ciphertext.len(),
+        |key, plaintext| {
+            lcg.keystream_into(u32::from_be_bytes(key.try_into().unwrap()), plaintext);
+            for (p, c) in plaintext.iter_mut().zip(ciphertext) {
+                *p ^= c;
+            }
+        },
+        scorer,
+        n,
+    )
+}
+
+pub fn recover_lcg_seed(ciphertext: &[u8], known_prefix: &[u8], lcg: &Lcg) -> Option<u32> {
+    // a seed giving the same keystream as the real one, from plaintext known at the start. the
+    // low bits of each state only depend on the low bits of the one before, so each keystream
+    // byte depends only on the state mod 2^(shift + 8), and the first byte leaves just the low
+    // shift bits of the first state to try. the rest of known_prefix checks each try, so a few
+    // bytes are enough. None if no seed fits
+    assert!(
+        lcg.shift <= 24,
+        "Keystream bytes must come from the low 32 bits."
+    );
+    let keystream: Vec<u8> = ciphertext
+        .iter()
+        .zip(known_prefix)
+        .map(|(c, p)| c ^ p)
+        .collect();
+    let (&first, rest) = keystream.split_first()?;
+    let mut check = vec![0u8; rest.len()];
+    (0..1u32 << lcg.shift)
+        .map(|low| ((first as u32) << lcg.shift) | low)
+        .find(|&state| {
+            lcg.keystream_into(state, &mut check);
+            check == rest
+        })
+        .map(|state| lcg.step_back(state))
+}